@@ -17,10 +17,94 @@ pub struct Module {
     pub assignments: Vec<Assignment>,
     /// Clocked sequential processes.
     pub processes: Vec<Process>,
+    /// Kind of each top-level item in source order, which
+    /// [`Module::items`] follows to interleave the three lists above.
+    pub order: Vec<ItemKind>,
     /// Source span covering the module.
     pub span: Span,
 }
 
+impl Module {
+    /// Iterate over every top-level item in source order.
+    ///
+    /// Items are interleaved as recorded in [`Module::order`]. Items pushed
+    /// onto the lists without a matching entry there follow at the end, as
+    /// declarations, then assignments, then processes.
+    pub fn items(&self) -> impl Iterator<Item = Item<'_>> {
+        let mut declarations = self.declarations.iter();
+        let mut assignments = self.assignments.iter();
+        let mut processes = self.processes.iter();
+        let mut items = self
+            .order
+            .iter()
+            .filter_map(|kind| match kind {
+                ItemKind::Declaration => declarations.next().map(Item::Declaration),
+                ItemKind::Assignment => assignments.next().map(Item::Assignment),
+                ItemKind::Process => processes.next().map(Item::Process),
+            })
+            .collect::<Vec<_>>();
+        items.extend(declarations.map(Item::Declaration));
+        items.extend(assignments.map(Item::Assignment));
+        items.extend(processes.map(Item::Process));
+        items.into_iter()
+    }
+
+    /// Iterate over declarations of one kind in source order.
+    pub fn declarations_of(&self, kind: DeclKind) -> impl Iterator<Item = &Declaration> {
+        self.declarations
+            .iter()
+            .filter(move |decl| decl.kind == kind)
+    }
+
+    /// Iterate over input port declarations.
+    pub fn inputs(&self) -> impl Iterator<Item = &Declaration> {
+        self.declarations_of(DeclKind::Input)
+    }
+
+    /// Iterate over output port declarations.
+    pub fn outputs(&self) -> impl Iterator<Item = &Declaration> {
+        self.declarations_of(DeclKind::Output)
+    }
+
+    /// Find a declaration by name.
+    pub fn declaration(&self, name: &str) -> Option<&Declaration> {
+        self.declarations.iter().find(|decl| decl.name == name)
+    }
+}
+
+/// Borrowed top-level module item.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Item<'a> {
+    /// Signal or constant declaration.
+    Declaration(&'a Declaration),
+    /// Combinational assignment.
+    Assignment(&'a Assignment),
+    /// Clocked sequential process.
+    Process(&'a Process),
+}
+
+impl Item<'_> {
+    /// Return the source span for this item.
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Declaration(decl) => decl.span,
+            Item::Assignment(assignment) => assignment.span,
+            Item::Process(process) => process.span,
+        }
+    }
+}
+
+/// Kind of a top-level module item, recorded in [`Module::order`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ItemKind {
+    /// Signal or constant declaration.
+    Declaration,
+    /// Combinational assignment.
+    Assignment,
+    /// Clocked sequential process.
+    Process,
+}
+
 /// A signal or constant declaration.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Declaration {
@@ -236,6 +320,8 @@ pub enum BinaryOp {
     Div,
     /// Remainder.
     Mod,
+    /// Exponentiation.
+    Pow,
     /// Shift left.
     Shl,
     /// Shift right.
@@ -262,6 +348,335 @@ pub enum BinaryOp {
     LogicAnd,
     /// Logical or.
     LogicOr,
+    /// Smaller of two values, written `min(a, b)`.
+    Min,
+    /// Larger of two values, written `max(a, b)`.
+    Max,
+}
+
+/// Fluent builder for constructing a [`Module`] without source text.
+///
+/// Every node created by the builder and the helper constructors below uses
+/// an empty [`Span`], so diagnostics for built modules carry no useful
+/// location. Build the module, then pass it to [`crate::compile_ast`].
+#[derive(Clone, Debug)]
+pub struct ModuleBuilder {
+    module: Module,
+}
+
+impl ModuleBuilder {
+    /// Start an empty module with the given name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            module: Module {
+                name: name.into(),
+                declarations: Vec::new(),
+                assignments: Vec::new(),
+                processes: Vec::new(),
+                order: Vec::new(),
+                span: Span::default(),
+            },
+        }
+    }
+
+    /// Declare an input port.
+    pub fn input(self, name: impl Into<String>, ty: Type) -> Self {
+        self.declare(DeclKind::Input, name, ty, None)
+    }
+
+    /// Declare an output port.
+    pub fn output(self, name: impl Into<String>, ty: Type) -> Self {
+        self.declare(DeclKind::Output, name, ty, None)
+    }
+
+    /// Declare an internal wire.
+    pub fn wire(self, name: impl Into<String>, ty: Type) -> Self {
+        self.declare(DeclKind::Wire, name, ty, None)
+    }
+
+    /// Declare a register.
+    pub fn reg(self, name: impl Into<String>, ty: Type) -> Self {
+        self.declare(DeclKind::Reg, name, ty, None)
+    }
+
+    /// Declare a constant with its initializer.
+    pub fn constant(self, name: impl Into<String>, ty: Type, value: Expr) -> Self {
+        self.declare(DeclKind::Const, name, ty, Some(value))
+    }
+
+    /// Add a top-level combinational assignment.
+    pub fn assign(mut self, target: impl Into<String>, expr: Expr) -> Self {
+        self.module.assignments.push(assign(target, expr));
+        self.module.order.push(ItemKind::Assignment);
+        self
+    }
+
+    /// Add a clocked process with the given register assignments.
+    pub fn process(
+        mut self,
+        edge: Edge,
+        clock: impl Into<String>,
+        assignments: impl IntoIterator<Item = Assignment>,
+    ) -> Self {
+        self.module.processes.push(Process {
+            edge,
+            clock: clock.into(),
+            assignments: assignments.into_iter().collect(),
+            span: Span::default(),
+        });
+        self.module.order.push(ItemKind::Process);
+        self
+    }
+
+    /// Finish building and return the module.
+    pub fn build(self) -> Module {
+        self.module
+    }
+
+    fn declare(
+        mut self,
+        kind: DeclKind,
+        name: impl Into<String>,
+        ty: Type,
+        value: Option<Expr>,
+    ) -> Self {
+        self.module.declarations.push(Declaration {
+            kind,
+            name: name.into(),
+            ty,
+            value,
+            span: Span::default(),
+        });
+        self.module.order.push(ItemKind::Declaration);
+        self
+    }
+}
+
+/// Build an assignment for use with [`ModuleBuilder::process`].
+pub fn assign(target: impl Into<String>, expr: Expr) -> Assignment {
+    Assignment {
+        target: target.into(),
+        expr,
+        span: Span::default(),
+    }
+}
+
+/// Build an unsigned integer literal.
+pub fn num(value: u128) -> Expr {
+    Expr::Number {
+        value,
+        span: Span::default(),
+    }
+}
+
+/// Build a signal or constant reference.
+pub fn signal(name: impl Into<String>) -> Expr {
+    Expr::Signal {
+        name: name.into(),
+        span: Span::default(),
+    }
+}
+
+/// Build a unary expression.
+pub fn unary(op: UnaryOp, expr: Expr) -> Expr {
+    Expr::Unary {
+        op,
+        expr: Box::new(expr),
+        span: Span::default(),
+    }
+}
+
+/// Build a binary expression.
+pub fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+    Expr::Binary {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+        span: Span::default(),
+    }
+}
+
+/// Build a conditional mux expression.
+pub fn conditional(condition: Expr, then_expr: Expr, else_expr: Expr) -> Expr {
+    Expr::Conditional {
+        condition: Box::new(condition),
+        then_expr: Box::new(then_expr),
+        else_expr: Box::new(else_expr),
+        span: Span::default(),
+    }
+}
+
+/// Build a one-bit index expression.
+pub fn index(expr: Expr, index: u32) -> Expr {
+    Expr::Index {
+        expr: Box::new(expr),
+        index,
+        span: Span::default(),
+    }
+}
+
+/// Build an inclusive descending slice expression.
+pub fn slice(expr: Expr, msb: u32, lsb: u32) -> Expr {
+    Expr::Slice {
+        expr: Box::new(expr),
+        msb,
+        lsb,
+        span: Span::default(),
+    }
+}
+
+/// Prints the module as Frag source that parses back to the same AST.
+impl fmt::Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "module {} {{", self.name)?;
+        for item in self.items() {
+            match item {
+                Item::Declaration(decl) => match &decl.value {
+                    Some(value) => writeln!(
+                        f,
+                        "    {} {}: {} = {};",
+                        decl.kind, decl.name, decl.ty, value
+                    )?,
+                    None => writeln!(f, "    {} {}: {};", decl.kind, decl.name, decl.ty)?,
+                },
+                Item::Assignment(assignment) => writeln!(f, "    {}", assignment)?,
+                Item::Process(process) => {
+                    writeln!(f, "    on {}({}) {{", process.edge, process.clock)?;
+                    for assignment in &process.assignments {
+                        writeln!(f, "        {}", assignment)?;
+                    }
+                    writeln!(f, "    }}")?;
+                }
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {};", self.target, self.expr)
+    }
+}
+
+/// Binding strength of postfix bit selection.
+const POSTFIX_PRECEDENCE: u8 = 13;
+/// Binding strength of primary expressions, which never need parentheses.
+const PRIMARY_PRECEDENCE: u8 = 14;
+/// Binding strength of prefix unary operators.
+const UNARY_PRECEDENCE: u8 = 12;
+
+impl BinaryOp {
+    /// Return the binding strength of the operator; higher binds tighter.
+    ///
+    /// Mirrors the parser's precedence levels.
+    pub fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::LogicOr => 1,
+            BinaryOp::LogicAnd => 2,
+            BinaryOp::BitOr => 3,
+            BinaryOp::BitXor => 4,
+            BinaryOp::BitAnd => 5,
+            BinaryOp::Eq | BinaryOp::Ne => 6,
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 7,
+            BinaryOp::Shl | BinaryOp::Shr => 8,
+            BinaryOp::Add | BinaryOp::Sub => 9,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 10,
+            BinaryOp::Pow => 11,
+            BinaryOp::Min | BinaryOp::Max => PRIMARY_PRECEDENCE,
+        }
+    }
+
+    /// Return true for right-associative operators, currently only `**`.
+    pub fn is_right_associative(self) -> bool {
+        self == BinaryOp::Pow
+    }
+}
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary { op, .. } => op.precedence(),
+            Expr::Unary { .. } => UNARY_PRECEDENCE,
+            Expr::Index { .. } | Expr::Slice { .. } => POSTFIX_PRECEDENCE,
+            _ => PRIMARY_PRECEDENCE,
+        }
+    }
+
+    /// Write the expression, parenthesized if it binds looser than `min`.
+    fn write_at(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+        if self.precedence() < min {
+            write!(f, "(")?;
+            self.write_at(f, 0)?;
+            return write!(f, ")");
+        }
+        match self {
+            Expr::Number { value, .. } => write!(f, "{}", value),
+            Expr::Bool { value, .. } => write!(f, "{}", value),
+            Expr::Signal { name, .. } => write!(f, "{}", name),
+            Expr::Index { expr, index, .. } => {
+                expr.write_at(f, POSTFIX_PRECEDENCE)?;
+                write!(f, "[{}]", index)
+            }
+            Expr::Slice { expr, msb, lsb, .. } => {
+                expr.write_at(f, POSTFIX_PRECEDENCE)?;
+                write!(f, "[{}:{}]", msb, lsb)
+            }
+            Expr::Unary { op, expr, .. } => {
+                write!(f, "{}", op)?;
+                expr.write_at(f, UNARY_PRECEDENCE)
+            }
+            Expr::Binary {
+                op: op @ (BinaryOp::Min | BinaryOp::Max),
+                left,
+                right,
+                ..
+            } => write!(f, "{}({}, {})", op, left, right),
+            Expr::Binary {
+                op, left, right, ..
+            } => {
+                let precedence = op.precedence();
+                let (left_min, right_min) = if op.is_right_associative() {
+                    (precedence + 1, precedence)
+                } else {
+                    (precedence, precedence + 1)
+                };
+                left.write_at(f, left_min)?;
+                write!(f, " {} ", op)?;
+                right.write_at(f, right_min)
+            }
+            Expr::Conditional {
+                condition,
+                then_expr,
+                else_expr,
+                ..
+            } => write!(
+                f,
+                "if {} {{ {} }} else {{ {} }}",
+                condition, then_expr, else_expr
+            ),
+            Expr::Case { selector, arms, .. } => {
+                write!(f, "case {} {{ ", selector)?;
+                for (idx, arm) in arms.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match &arm.pattern {
+                        Some(pattern) => write!(f, "{} => {}", pattern, arm.value)?,
+                        None => write!(f, "else => {}", arm.value)?,
+                    }
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+/// Prints Frag source with the fewest parentheses that keep the same tree.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_at(f, 0)
+    }
 }
 
 impl fmt::Display for DeclKind {
@@ -313,6 +728,7 @@ impl fmt::Display for BinaryOp {
             BinaryOp::Mul => write!(f, "*"),
             BinaryOp::Div => write!(f, "/"),
             BinaryOp::Mod => write!(f, "%"),
+            BinaryOp::Pow => write!(f, "**"),
             BinaryOp::Shl => write!(f, "<<"),
             BinaryOp::Shr => write!(f, ">>"),
             BinaryOp::Lt => write!(f, "<"),
@@ -326,6 +742,8 @@ impl fmt::Display for BinaryOp {
             BinaryOp::BitOr => write!(f, "|"),
             BinaryOp::LogicAnd => write!(f, "&&"),
             BinaryOp::LogicOr => write!(f, "||"),
+            BinaryOp::Min => write!(f, "min"),
+            BinaryOp::Max => write!(f, "max"),
         }
     }
 }
@@ -20,6 +20,11 @@ impl Span {
         Self { start, end }
     }
 
+    /// Return the line and column where this span starts.
+    pub fn location(self, source: &str) -> Location {
+        Location::of(source, self.start)
+    }
+
     /// Return a span that covers both spans.
     pub fn join(self, other: Span) -> Span {
         Span {
@@ -29,6 +34,36 @@ impl Span {
     }
 }
 
+/// One-based line and column for a byte offset in source text.
+///
+/// Columns count characters, not bytes, so multi-byte characters before the
+/// offset occupy one column each.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Location {
+    /// One-based line number.
+    pub line: usize,
+    /// One-based character column.
+    pub column: usize,
+}
+
+impl Location {
+    /// Compute the location of `offset` in `source`.
+    pub fn of(source: &str, offset: usize) -> Self {
+        let (line, line_start) = line_start_for_offset(source, offset);
+        let offset = offset.min(source.len());
+        Self {
+            line,
+            column: source[line_start..offset].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
 /// Compiler error with an optional source span.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Diagnostic {
@@ -36,6 +71,9 @@ pub struct Diagnostic {
     pub message: String,
     /// Optional source location for the error.
     pub span: Option<Span>,
+    /// Stable error code, such as `E0001`, for errors with an [`explain`]
+    /// entry.
+    pub code: Option<&'static str>,
 }
 
 impl Diagnostic {
@@ -44,6 +82,7 @@ impl Diagnostic {
         Self {
             message: message.into(),
             span: None,
+            code: None,
         }
     }
 
@@ -52,36 +91,84 @@ impl Diagnostic {
         Self {
             message: message.into(),
             span: Some(span),
+            code: None,
         }
     }
 
+    /// Attach a stable error code to the diagnostic.
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
     /// Render the diagnostic with a source snippet when a span is available.
     pub fn with_source(&self, source: &str) -> String {
+        let message = match self.code {
+            Some(code) => format!("[{}] {}", code, self.message),
+            None => self.message.clone(),
+        };
         let Some(span) = self.span else {
-            return self.message.clone();
+            return message;
         };
 
-        let (line_no, line_start) = line_start_for_offset(source, span.start);
+        let (_, line_start) = line_start_for_offset(source, span.start);
         let line_end = source[line_start..]
             .find('\n')
             .map(|offset| line_start + offset)
             .unwrap_or(source.len());
         let line = &source[line_start..line_end];
-        let column = span.start.saturating_sub(line_start) + 1;
-        let marker_len = span.end.saturating_sub(span.start).max(1);
+        let location = span.location(source);
+        let (line, marker_start, marker_end) = expand_tabs(
+            line,
+            span.start.saturating_sub(line_start),
+            span.end.saturating_sub(line_start),
+        );
 
         format!(
             "{}\n --> line {}, column {}\n{}\n{}{}",
-            self.message,
-            line_no,
-            column,
+            message,
+            location.line,
+            location.column,
             line,
-            " ".repeat(column.saturating_sub(1)),
-            "^".repeat(marker_len.min(line.len().saturating_sub(column - 1)).max(1))
+            " ".repeat(marker_start),
+            "^".repeat(marker_end.saturating_sub(marker_start).max(1))
         )
     }
 }
 
+/// Tab stops used when printing a source line under a diagnostic.
+const TAB_WIDTH: usize = 4;
+
+/// Expand tabs in `line` to spaces and map the byte range `start..end` of the
+/// original line to display columns, so the caret line stays aligned.
+fn expand_tabs(line: &str, start: usize, end: usize) -> (String, usize, usize) {
+    let mut expanded = String::with_capacity(line.len());
+    let mut width = 0;
+    let mut marker_start = None;
+    let mut marker_end = None;
+    for (offset, ch) in line.char_indices() {
+        if offset >= start && marker_start.is_none() {
+            marker_start = Some(width);
+        }
+        if offset >= end && marker_end.is_none() {
+            marker_end = Some(width);
+        }
+        if ch == '\t' {
+            let spaces = TAB_WIDTH - width % TAB_WIDTH;
+            expanded.push_str(&" ".repeat(spaces));
+            width += spaces;
+        } else {
+            expanded.push(ch);
+            width += 1;
+        }
+    }
+    (
+        expanded,
+        marker_start.unwrap_or(width),
+        marker_end.unwrap_or(width),
+    )
+}
+
 impl fmt::Display for Diagnostic {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.message)
@@ -90,6 +177,17 @@ impl fmt::Display for Diagnostic {
 
 impl std::error::Error for Diagnostic {}
 
+/// Return the long explanation for an error code, or `None` if it is unknown.
+///
+/// Each explanation describes the mistake, shows an example, and suggests a
+/// fix. The CLI prints these with `frag --explain CODE`.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code))
+        .map(|(_, text)| *text)
+}
+
 /// Result type used throughout the compiler.
 pub type Result<T> = std::result::Result<T, Diagnostic>;
 
@@ -109,3 +207,185 @@ fn line_start_for_offset(source: &str, offset: usize) -> (usize, usize) {
 
     (line_no, line_start)
 }
+
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "E0001",
+        "A name was used that no declaration introduces.
+
+Every signal, constant, assignment target, and clock must be declared in the
+module before the compiler can resolve it:
+
+    module Example {
+        input a: bit;
+        output y: bit;
+        y = a ^ b; // error: `b` is never declared
+    }
+
+Declare the missing name, or fix the spelling if it refers to an existing
+declaration:
+
+    input b: bit;",
+    ),
+    (
+        "E0002",
+        "The same name was declared more than once in a module.
+
+Inputs, outputs, wires, registers, and constants share one namespace:
+
+    input data: u8;
+    wire data: u8; // error: `data` is already an input
+
+Rename one of the declarations.",
+    ),
+    (
+        "E0003",
+        "An expression's width does not match the width of its target.
+
+Frag never truncates or extends values implicitly:
+
+    input value: u8;
+    output low: u4;
+    low = value; // error: 4-bit target, 8-bit expression
+
+Select the bits you want, or widen the target:
+
+    low = value[3:0];
+
+Unsized literals are accepted whenever their value fits in the target.",
+    ),
+    (
+        "E0004",
+        "A combinational signal is assigned more than once.
+
+Each output or wire is driven by exactly one assignment, as in hardware:
+
+    out = a;
+    out = b; // error: second driver for `out`
+
+Combine the alternatives into a single expression:
+
+    out = if sel { a } else { b };",
+    ),
+    (
+        "E0005",
+        "An assignment target has the wrong declaration kind for where it appears.
+
+Assignments outside a process must target an `output` or `wire`, and
+assignments inside an `on rising` or `on falling` process must target a
+`reg`:
+
+    reg count: u8;
+    count = count + 1; // error: registers update only in a process
+
+Move the assignment into a process, or declare the target with the kind that
+matches where it is assigned:
+
+    on rising(clk) {
+        count = count + 1;
+    }",
+    ),
+    (
+        "E0006",
+        "A register is driven more than once.
+
+A register may be assigned once per process and by only one process:
+
+    on rising(clk) { count = 0; }
+    on falling(clk) { count = 1; } // error: second process drives `count`
+
+Keep all updates for a register in one process and select between them with
+an `if` or `case` expression.",
+    ),
+    (
+        "E0007",
+        "An output is declared but never assigned.
+
+Every output needs a combinational driver:
+
+    output ready: bit; // error: `ready` has no assignment
+
+Assign a value to the output, even a constant:
+
+    ready = 1;",
+    ),
+    (
+        "E0008",
+        "Signals or constants depend on each other in a cycle.
+
+Combinational logic must form a directed acyclic graph:
+
+    a = b;
+    b = a; // error: circular reference
+
+Break the loop with a register, which updates only on a clock edge.",
+    ),
+    (
+        "E0009",
+        "A process clock is not a one-bit input.
+
+The signal named in `on rising(...)` or `on falling(...)` must be declared
+as a one-bit `input`:
+
+    input clk: u2;
+    on rising(clk) { ... } // error: clock is two bits wide
+
+Declare the clock as `input clk: bit;`.",
+    ),
+    (
+        "E0010",
+        "A bit index or slice is outside the selected expression.
+
+Bit positions count from 0 for the least significant bit, and slices are
+written from high to low:
+
+    input data: u8;
+    top = data[8];    // error: `data` has bits 7 down to 0
+    low = data[0:3];  // error: slices must be descending
+
+Use `data[7]` and `data[3:0]`.",
+    ),
+    (
+        "E0011",
+        "A case expression has an invalid pattern or `else` arm.
+
+Constant patterns must be distinct and fit the selector width, and every case
+expression needs exactly one `else` arm, written last:
+
+    input sel: u1;
+    out = case sel {
+        0 => a,
+        2 => b, // error: 2 does not fit in one bit
+    };
+
+Remove or fix the pattern and add the `else` arm:
+
+    out = case sel { 0 => a, else => b };",
+    ),
+    (
+        "E0012",
+        "A condition is wider than one bit in strict mode.
+
+With `--strict`, `if` conditions and the operands of `!`, `&&`, and `||` must
+be one bit, so a multi-bit value is never silently treated as a boolean:
+
+    input count: u4;
+    out = if count { a } else { b }; // error in strict mode
+
+Compare explicitly:
+
+    out = if count != 0 { a } else { b };",
+    ),
+    (
+        "E0013",
+        "A constant depends on a value that is not constant.
+
+Constants are evaluated at compile time, so they may refer only to literals
+and other constants:
+
+    input a: u4;
+    const mask: u4 = a & 3; // error: `a` is an input
+
+Use a `wire` for values computed from signals.",
+    ),
+];
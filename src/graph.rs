@@ -409,6 +409,17 @@ fn expr_label(expr: &IrExpr) -> String {
             }
         }
         IrExpr::Unary { op, expr, .. } => format!("{}{}", op_name_unary(*op), expr_label(expr)),
+        IrExpr::Binary {
+            op: op @ (BinaryOp::Min | BinaryOp::Max),
+            left,
+            right,
+            ..
+        } => format!(
+            "{}({}, {})",
+            op_name(*op),
+            expr_label(left),
+            expr_label(right)
+        ),
         IrExpr::Binary {
             op, left, right, ..
         } => format!(
@@ -451,6 +462,7 @@ fn op_name(op: BinaryOp) -> &'static str {
         BinaryOp::Mul => "*",
         BinaryOp::Div => "/",
         BinaryOp::Mod => "%",
+        BinaryOp::Pow => "**",
         BinaryOp::Shl => "<<",
         BinaryOp::Shr => ">>",
         BinaryOp::Lt => "<",
@@ -464,6 +476,8 @@ fn op_name(op: BinaryOp) -> &'static str {
         BinaryOp::BitOr => "|",
         BinaryOp::LogicAnd => "&&",
         BinaryOp::LogicOr => "||",
+        BinaryOp::Min => "min",
+        BinaryOp::Max => "max",
     }
 }
 
@@ -173,6 +173,71 @@ impl IrExpr {
     }
 }
 
+/// Rough hardware size summary for a lowered module.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IrStats {
+    /// Total register state in bits.
+    pub register_bits: u64,
+    /// Unary, binary, mux, and case nodes in combinational and sequential
+    /// logic. Constants, signal references, and slices are wiring only.
+    pub operators: usize,
+    /// Sum of operator result widths, a proxy for datapath size.
+    pub operator_bits: u64,
+}
+
+impl IrModule {
+    /// Count registers and operators in the module's logic.
+    ///
+    /// Constant initializers are excluded because they fold to literals in
+    /// generated hardware.
+    pub fn stats(&self) -> IrStats {
+        let mut stats = IrStats {
+            register_bits: self
+                .signals
+                .iter()
+                .filter(|signal| signal.kind == IrSignalKind::Reg)
+                .map(|signal| u64::from(signal.width))
+                .sum(),
+            ..IrStats::default()
+        };
+        let assignments = self.combinational.iter().chain(
+            self.processes
+                .iter()
+                .flat_map(|process| &process.assignments),
+        );
+        for assignment in assignments {
+            count_operators(&assignment.expr, &mut stats);
+        }
+        stats
+    }
+}
+
+fn count_operators(expr: &IrExpr, stats: &mut IrStats) {
+    let children: Vec<&IrExpr> = match expr {
+        IrExpr::Const { .. } | IrExpr::Signal { .. } => return,
+        IrExpr::Slice { expr, .. } => return count_operators(expr, stats),
+        IrExpr::Unary { expr, .. } => vec![expr],
+        IrExpr::Binary { left, right, .. } => vec![left, right],
+        IrExpr::Mux {
+            select,
+            when_true,
+            when_false,
+            ..
+        } => vec![select, when_true, when_false],
+        IrExpr::Case { selector, arms, .. } => std::iter::once(&**selector)
+            .chain(
+                arms.iter()
+                    .flat_map(|arm| arm.pattern.iter().chain([&arm.value])),
+            )
+            .collect(),
+    };
+    stats.operators += 1;
+    stats.operator_bits += u64::from(expr.width());
+    for child in children {
+        count_operators(child, stats);
+    }
+}
+
 /// Validate structural IR invariants.
 ///
 /// Semantic analysis is responsible for user-facing source diagnostics. This
@@ -536,7 +601,7 @@ fn validate_expr(expr: &IrExpr, bindings: &BTreeMap<String, IrBinding>) -> Resul
                 | BinaryOp::Ne
                 | BinaryOp::LogicAnd
                 | BinaryOp::LogicOr => 1,
-                BinaryOp::Shl | BinaryOp::Shr => left.width(),
+                BinaryOp::Shl | BinaryOp::Shr | BinaryOp::Pow => left.width(),
                 _ => left.width().max(right.width()),
             };
             validate_expected_width(*width, expected, "binary expression")?;
@@ -832,6 +897,7 @@ fn op_name(op: BinaryOp) -> &'static str {
         Mul => "MUL",
         Div => "DIV",
         Mod => "MOD",
+        Pow => "POW",
         Shl => "SHL",
         Shr => "SHR",
         Lt => "LT",
@@ -845,6 +911,8 @@ fn op_name(op: BinaryOp) -> &'static str {
         BitOr => "OR",
         LogicAnd => "LOGIC_AND",
         LogicOr => "LOGIC_OR",
+        Min => "MIN",
+        Max => "MAX",
     }
 }
 
@@ -0,0 +1,181 @@
+//! JSON export of the source-level AST.
+//!
+//! The output is a stable, dependency-free view of a parsed module for tools
+//! that run in another process. Every node is an object with a `kind` field;
+//! operators, declaration kinds, and edges are written as their source
+//! spelling, and spans are `{"start": N, "end": N}` byte offsets.
+
+use crate::ast::{Assignment, CaseArm, Declaration, Expr, Module, Process};
+use crate::diagnostic::Span;
+
+/// Serialize a module as one line of JSON.
+///
+/// Literal values are written as JSON numbers and can exceed the 2^53 range
+/// that some JSON readers represent exactly.
+pub fn module_to_json(module: &Module) -> String {
+    let mut out = String::new();
+    out.push_str("{\"kind\":\"module\",\"name\":");
+    string(&mut out, &module.name);
+    out.push_str(",\"declarations\":");
+    list(&mut out, &module.declarations, declaration);
+    out.push_str(",\"assignments\":");
+    list(&mut out, &module.assignments, assignment);
+    out.push_str(",\"processes\":");
+    list(&mut out, &module.processes, process);
+    span(&mut out, module.span);
+    out.push('}');
+    out
+}
+
+fn declaration(out: &mut String, decl: &Declaration) {
+    out.push_str(&format!("{{\"kind\":\"{}\",\"name\":", decl.kind));
+    string(out, &decl.name);
+    out.push_str(&format!(",\"width\":{},\"value\":", decl.ty.width));
+    match &decl.value {
+        Some(value) => expr(out, value),
+        None => out.push_str("null"),
+    }
+    span(out, decl.span);
+    out.push('}');
+}
+
+fn assignment(out: &mut String, assignment: &Assignment) {
+    out.push_str("{\"kind\":\"assignment\",\"target\":");
+    string(out, &assignment.target);
+    out.push_str(",\"expr\":");
+    expr(out, &assignment.expr);
+    span(out, assignment.span);
+    out.push('}');
+}
+
+fn process(out: &mut String, process: &Process) {
+    out.push_str(&format!(
+        "{{\"kind\":\"process\",\"edge\":\"{}\",\"clock\":",
+        process.edge
+    ));
+    string(out, &process.clock);
+    out.push_str(",\"assignments\":");
+    list(out, &process.assignments, assignment);
+    span(out, process.span);
+    out.push('}');
+}
+
+fn expr(out: &mut String, node: &Expr) {
+    match node {
+        Expr::Number { value, .. } => {
+            out.push_str(&format!("{{\"kind\":\"number\",\"value\":{}", value));
+        }
+        Expr::Bool { value, .. } => {
+            out.push_str(&format!("{{\"kind\":\"bool\",\"value\":{}", value));
+        }
+        Expr::Signal { name, .. } => {
+            out.push_str("{\"kind\":\"signal\",\"name\":");
+            string(out, name);
+        }
+        Expr::Index {
+            expr: inner, index, ..
+        } => {
+            out.push_str(&format!(
+                "{{\"kind\":\"index\",\"index\":{},\"expr\":",
+                index
+            ));
+            expr(out, inner);
+        }
+        Expr::Slice {
+            expr: inner,
+            msb,
+            lsb,
+            ..
+        } => {
+            out.push_str(&format!(
+                "{{\"kind\":\"slice\",\"msb\":{},\"lsb\":{},\"expr\":",
+                msb, lsb
+            ));
+            expr(out, inner);
+        }
+        Expr::Unary {
+            op, expr: inner, ..
+        } => {
+            out.push_str(&format!("{{\"kind\":\"unary\",\"op\":\"{}\",\"expr\":", op));
+            expr(out, inner);
+        }
+        Expr::Binary {
+            op, left, right, ..
+        } => {
+            out.push_str(&format!(
+                "{{\"kind\":\"binary\",\"op\":\"{}\",\"left\":",
+                op
+            ));
+            expr(out, left);
+            out.push_str(",\"right\":");
+            expr(out, right);
+        }
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+            ..
+        } => {
+            out.push_str("{\"kind\":\"conditional\",\"condition\":");
+            expr(out, condition);
+            out.push_str(",\"then\":");
+            expr(out, then_expr);
+            out.push_str(",\"else\":");
+            expr(out, else_expr);
+        }
+        Expr::Case { selector, arms, .. } => {
+            out.push_str("{\"kind\":\"case\",\"selector\":");
+            expr(out, selector);
+            out.push_str(",\"arms\":");
+            list(out, arms, case_arm);
+        }
+    }
+    span(out, node.span());
+    out.push('}');
+}
+
+fn case_arm(out: &mut String, arm: &CaseArm) {
+    out.push_str("{\"kind\":\"arm\",\"pattern\":");
+    match &arm.pattern {
+        Some(pattern) => expr(out, pattern),
+        None => out.push_str("null"),
+    }
+    out.push_str(",\"value\":");
+    expr(out, &arm.value);
+    span(out, arm.span);
+    out.push('}');
+}
+
+fn list<T>(out: &mut String, items: &[T], item: fn(&mut String, &T)) {
+    out.push('[');
+    for (idx, value) in items.iter().enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        item(out, value);
+    }
+    out.push(']');
+}
+
+/// Append a `"span"` field; callers have already written at least one field.
+fn span(out: &mut String, span: Span) {
+    out.push_str(&format!(
+        ",\"span\":{{\"start\":{},\"end\":{}}}",
+        span.start, span.end
+    ));
+}
+
+fn string(out: &mut String, text: &str) {
+    out.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            ch if ch.is_control() => {
+                out.push_str(&format!("\\u{:04x}", ch as u32));
+            }
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
@@ -4,6 +4,7 @@
 //! and leaves grammar and hardware validity decisions to later stages.
 
 use crate::diagnostic::{Diagnostic, Result, Span};
+use std::collections::BTreeSet;
 use std::fmt;
 
 /// Token with its source span.
@@ -30,11 +31,14 @@ pub enum TokenKind {
     If,
     Else,
     Case,
+    Include,
     Bit,
     BoolType,
     BoolLiteral(bool),
     Identifier(String),
     Number(u128),
+    Str(String),
+    Question,
     Colon,
     Semicolon,
     Comma,
@@ -45,10 +49,16 @@ pub enum TokenKind {
     LeftBracket,
     RightBracket,
     Equal,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
     FatArrow,
     Plus,
     Minus,
     Star,
+    StarStar,
     Slash,
     Percent,
     Amp,
@@ -69,6 +79,31 @@ pub enum TokenKind {
     Eof,
 }
 
+impl TokenKind {
+    /// Return true for reserved words, which cannot be used as names.
+    pub fn is_keyword(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Module
+                | TokenKind::Input
+                | TokenKind::Output
+                | TokenKind::Wire
+                | TokenKind::Reg
+                | TokenKind::Const
+                | TokenKind::On
+                | TokenKind::Rising
+                | TokenKind::Falling
+                | TokenKind::If
+                | TokenKind::Else
+                | TokenKind::Case
+                | TokenKind::Include
+                | TokenKind::Bit
+                | TokenKind::BoolType
+                | TokenKind::BoolLiteral(_)
+        )
+    }
+}
+
 impl fmt::Display for TokenKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -84,11 +119,14 @@ impl fmt::Display for TokenKind {
             TokenKind::If => write!(f, "if"),
             TokenKind::Else => write!(f, "else"),
             TokenKind::Case => write!(f, "case"),
+            TokenKind::Include => write!(f, "include"),
             TokenKind::Bit => write!(f, "bit"),
             TokenKind::BoolType => write!(f, "bool"),
             TokenKind::BoolLiteral(value) => write!(f, "{}", value),
             TokenKind::Identifier(name) => write!(f, "identifier `{}`", name),
             TokenKind::Number(value) => write!(f, "number `{}`", value),
+            TokenKind::Str(text) => write!(f, "string `\"{}\"`", text),
+            TokenKind::Question => write!(f, "?"),
             TokenKind::Colon => write!(f, ":"),
             TokenKind::Semicolon => write!(f, ";"),
             TokenKind::Comma => write!(f, ","),
@@ -99,10 +137,16 @@ impl fmt::Display for TokenKind {
             TokenKind::LeftBracket => write!(f, "["),
             TokenKind::RightBracket => write!(f, "]"),
             TokenKind::Equal => write!(f, "="),
+            TokenKind::PlusEqual => write!(f, "+="),
+            TokenKind::MinusEqual => write!(f, "-="),
+            TokenKind::StarEqual => write!(f, "*="),
+            TokenKind::SlashEqual => write!(f, "/="),
+            TokenKind::PercentEqual => write!(f, "%="),
             TokenKind::FatArrow => write!(f, "=>"),
             TokenKind::Plus => write!(f, "+"),
             TokenKind::Minus => write!(f, "-"),
             TokenKind::Star => write!(f, "*"),
+            TokenKind::StarStar => write!(f, "**"),
             TokenKind::Slash => write!(f, "/"),
             TokenKind::Percent => write!(f, "%"),
             TokenKind::Amp => write!(f, "&"),
@@ -126,8 +170,19 @@ impl fmt::Display for TokenKind {
 }
 
 /// Lex source text into a token stream terminated by [`TokenKind::Eof`].
+///
+/// No features are defined, so every `#if` block is skipped.
 pub fn lex(source: &str) -> Result<Vec<Token>> {
-    Lexer::new(source).tokenize()
+    lex_with_defines(source, &BTreeSet::new())
+}
+
+/// Lex source text, keeping `#if NAME` blocks whose feature is in `defines`.
+///
+/// `#if NAME` and `#endif` are recognized only as the first thing on a line.
+/// Blocks nest, and an undefined feature skips everything up to its matching
+/// `#endif`. Any other `#` line is a comment.
+pub fn lex_with_defines(source: &str, defines: &BTreeSet<String>) -> Result<Vec<Token>> {
+    Lexer::new(source, defines).tokenize()
 }
 
 struct Lexer<'a> {
@@ -135,15 +190,25 @@ struct Lexer<'a> {
     bytes: &'a [u8],
     pos: usize,
     tokens: Vec<Token>,
+    defines: &'a BTreeSet<String>,
+    conditionals: Vec<Span>,
+}
+
+/// Preprocessor directive recognized at the start of a line.
+enum Directive<'s> {
+    If(Option<&'s str>, Option<&'s str>),
+    EndIf,
 }
 
 impl<'a> Lexer<'a> {
-    fn new(source: &'a str) -> Self {
+    fn new(source: &'a str, defines: &'a BTreeSet<String>) -> Self {
         Self {
             source,
             bytes: source.as_bytes(),
             pos: 0,
             tokens: Vec::new(),
+            defines,
+            conditionals: Vec::new(),
         }
     }
 
@@ -154,10 +219,13 @@ impl<'a> Lexer<'a> {
                     self.pos += 1;
                 }
                 b'/' if self.peek_next() == Some(b'/') => self.skip_line_comment(),
+                b'#' if self.at_line_start() => self.directive()?,
                 b'#' => self.skip_line_comment(),
                 b'/' if self.peek_next() == Some(b'*') => self.skip_block_comment()?,
                 b'a'..=b'z' | b'A'..=b'Z' | b'_' => self.lex_identifier(),
                 b'0'..=b'9' => self.lex_number()?,
+                b'"' => self.lex_string()?,
+                b'?' => self.single(TokenKind::Question),
                 b':' => self.single(TokenKind::Colon),
                 b';' => self.single(TokenKind::Semicolon),
                 b',' => self.single(TokenKind::Comma),
@@ -176,6 +244,12 @@ impl<'a> Lexer<'a> {
                 b'>' if self.peek_next() == Some(b'>') => self.double(TokenKind::ShiftRight),
                 b'&' if self.peek_next() == Some(b'&') => self.double(TokenKind::AmpAmp),
                 b'|' if self.peek_next() == Some(b'|') => self.double(TokenKind::PipePipe),
+                b'*' if self.peek_next() == Some(b'*') => self.double(TokenKind::StarStar),
+                b'+' if self.peek_next() == Some(b'=') => self.double(TokenKind::PlusEqual),
+                b'-' if self.peek_next() == Some(b'=') => self.double(TokenKind::MinusEqual),
+                b'*' if self.peek_next() == Some(b'=') => self.double(TokenKind::StarEqual),
+                b'/' if self.peek_next() == Some(b'=') => self.double(TokenKind::SlashEqual),
+                b'%' if self.peek_next() == Some(b'=') => self.double(TokenKind::PercentEqual),
                 b'=' => self.single(TokenKind::Equal),
                 b'+' => self.single(TokenKind::Plus),
                 b'-' => self.single(TokenKind::Minus),
@@ -199,6 +273,13 @@ impl<'a> Lexer<'a> {
             }
         }
 
+        if let Some(span) = self.conditionals.last() {
+            return Err(Diagnostic::at(
+                *span,
+                "Unterminated `#if`; expected a matching `#endif`",
+            ));
+        }
+
         self.tokens.push(Token {
             kind: TokenKind::Eof,
             span: Span::new(self.source.len(), self.source.len()),
@@ -241,15 +322,96 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    fn at_line_start(&self) -> bool {
+        self.bytes[..self.pos]
+            .iter()
+            .rev()
+            .take_while(|byte| **byte != b'\n')
+            .all(u8::is_ascii_whitespace)
+    }
+
+    fn line_end(&self, start: usize) -> usize {
+        self.source[start..]
+            .find('\n')
+            .map_or(self.source.len(), |offset| start + offset)
+    }
+
+    fn directive(&mut self) -> Result<()> {
+        let start = self.pos;
+        let end = self.line_end(start);
+        let span = Span::new(start, end);
+        let source = self.source;
+        match parse_directive(&source[start..end]) {
+            Some(Directive::If(None, _)) => {
+                return Err(Diagnostic::at(span, "`#if` requires a feature name"));
+            }
+            Some(Directive::If(Some(_), Some(extra))) => {
+                return Err(Diagnostic::at(
+                    span,
+                    format!("Unexpected `{}` after `#if` feature name", extra),
+                ));
+            }
+            Some(Directive::If(Some(name), None)) => {
+                self.skip_line_comment();
+                if self.defines.contains(name) {
+                    self.conditionals.push(span);
+                } else {
+                    self.skip_inactive(span)?;
+                }
+            }
+            Some(Directive::EndIf) => {
+                if self.conditionals.pop().is_none() {
+                    return Err(Diagnostic::at(span, "`#endif` without a matching `#if`"));
+                }
+                self.skip_line_comment();
+            }
+            None => self.skip_line_comment(),
+        }
+        Ok(())
+    }
+
+    fn skip_inactive(&mut self, opener: Span) -> Result<()> {
+        let mut depth = 1usize;
+        while self.pos < self.bytes.len() {
+            let end = self.line_end(self.pos);
+            match parse_directive(self.source[self.pos..end].trim_start()) {
+                Some(Directive::If(..)) => depth += 1,
+                Some(Directive::EndIf) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos = end;
+                        return Ok(());
+                    }
+                }
+                None => {}
+            }
+            self.pos = (end + 1).min(self.bytes.len());
+        }
+
+        Err(Diagnostic::at(
+            opener,
+            "Unterminated `#if`; expected a matching `#endif`",
+        ))
+    }
+
     fn skip_block_comment(&mut self) -> Result<()> {
         let start = self.pos;
-        self.pos += 2;
+        let mut depth = 0usize;
         while self.pos + 1 < self.bytes.len() {
-            if self.peek() == Some(b'*') && self.peek_next() == Some(b'/') {
-                self.pos += 2;
-                return Ok(());
+            match (self.peek(), self.peek_next()) {
+                (Some(b'/'), Some(b'*')) => {
+                    depth += 1;
+                    self.pos += 2;
+                }
+                (Some(b'*'), Some(b'/')) => {
+                    depth -= 1;
+                    self.pos += 2;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                _ => self.pos += 1,
             }
-            self.pos += 1;
         }
 
         Err(Diagnostic::at(
@@ -282,6 +444,7 @@ impl<'a> Lexer<'a> {
             "if" => TokenKind::If,
             "else" => TokenKind::Else,
             "case" => TokenKind::Case,
+            "include" => TokenKind::Include,
             "bit" => TokenKind::Bit,
             "bool" => TokenKind::BoolType,
             "true" => TokenKind::BoolLiteral(true),
@@ -295,45 +458,111 @@ impl<'a> Lexer<'a> {
         });
     }
 
+    /// Lex a double-quoted string on one line. Escapes are not supported.
+    fn lex_string(&mut self) -> Result<()> {
+        let start = self.pos;
+        let rest = &self.source[start + 1..];
+        let Some(len) = rest
+            .find(['"', '\n'])
+            .filter(|len| rest[*len..].starts_with('"'))
+        else {
+            let end = start + 1 + rest.find('\n').unwrap_or(rest.len());
+            return Err(Diagnostic::at(
+                Span::new(start, end),
+                "Unterminated string literal; strings must close on the same line",
+            ));
+        };
+        self.pos = start + 1 + len + 1;
+        self.tokens.push(Token {
+            kind: TokenKind::Str(rest[..len].to_string()),
+            span: Span::new(start, self.pos),
+        });
+        Ok(())
+    }
+
     fn lex_number(&mut self) -> Result<()> {
         let start = self.pos;
         self.pos += 1;
-        while matches!(
-            self.peek(),
-            Some(b'a'..=b'f' | b'A'..=b'F' | b'x' | b'X' | b'0'..=b'9' | b'_')
-        ) {
+        while matches!(self.peek(), Some(b) if b.is_ascii_alphanumeric() || b == b'_') {
             self.pos += 1;
         }
-
-        let text = &self.source[start..self.pos];
-        let (digits, radix) =
-            if let Some(rest) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
-                (rest, 16)
-            } else if let Some(rest) = text.strip_prefix("0b").or_else(|| text.strip_prefix("0B")) {
-                (rest, 2)
-            } else {
-                (text, 10)
-            };
-        let digits = digits.replace('_', "");
-
-        if digits.is_empty() {
-            return Err(Diagnostic::at(
-                Span::new(start, self.pos),
-                format!("Invalid number literal `{}`", text),
-            ));
+        if self.peek() == Some(b'.') && matches!(self.peek_next(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+            while matches!(self.peek(), Some(b'0'..=b'9' | b'_')) {
+                self.pos += 1;
+            }
         }
 
-        let value = u128::from_str_radix(&digits, radix).map_err(|_| {
-            Diagnostic::at(
-                Span::new(start, self.pos),
-                format!("Invalid number literal `{}`", text),
-            )
-        })?;
-
+        let span = Span::new(start, self.pos);
+        let value = parse_number(&self.source[start..self.pos], span)?;
         self.tokens.push(Token {
             kind: TokenKind::Number(value),
-            span: Span::new(start, self.pos),
+            span,
         });
         Ok(())
     }
 }
+
+fn parse_directive(line: &str) -> Option<Directive<'_>> {
+    let rest = line.strip_prefix('#')?;
+    if rest.starts_with(|c: char| c.is_whitespace()) {
+        return None;
+    }
+    let mut words = rest.split_whitespace();
+    match words.next()? {
+        "if" => Some(Directive::If(words.next(), words.next())),
+        "endif" => Some(Directive::EndIf),
+        _ => None,
+    }
+}
+
+/// Parse the text of one number literal, reporting malformed literals at `span`.
+fn parse_number(text: &str, span: Span) -> Result<u128> {
+    if text.contains('.') {
+        return Err(Diagnostic::at(
+            span,
+            format!(
+                "Fractional literal `{}` is not supported; Frag values are unsigned bit vectors",
+                text
+            ),
+        ));
+    }
+
+    let prefix = text.get(..2).map(str::to_ascii_lowercase);
+    let (digits, radix, name) = match prefix.as_deref() {
+        Some("0x") => (&text[2..], 16, "hexadecimal"),
+        Some("0o") => (&text[2..], 8, "octal"),
+        Some("0b") => (&text[2..], 2, "binary"),
+        _ => (text, 10, "decimal"),
+    };
+
+    if digits.chars().all(|c| c == '_') {
+        return Err(Diagnostic::at(
+            span,
+            format!("Number literal `{}` has no digits after its prefix", text),
+        ));
+    }
+    if digits.ends_with('_') {
+        return Err(Diagnostic::at(
+            span,
+            format!("Number literal `{}` ends with an underscore", text),
+        ));
+    }
+    if let Some((offset, bad)) = digits
+        .char_indices()
+        .find(|(_, c)| *c != '_' && !c.is_digit(radix))
+    {
+        let start = span.end - digits.len() + offset;
+        return Err(Diagnostic::at(
+            Span::new(start, start + bad.len_utf8()),
+            format!("Invalid digit `{}` in {} literal `{}`", bad, name, text),
+        ));
+    }
+
+    u128::from_str_radix(&digits.replace('_', ""), radix).map_err(|_| {
+        Diagnostic::at(
+            span,
+            format!("Number literal `{}` does not fit in 128 bits", text),
+        )
+    })
+}
@@ -15,18 +15,27 @@ pub mod diagnostic;
 pub mod graph;
 /// Netlist-style intermediate representation.
 pub mod ir;
+/// JSON export of the AST.
+pub mod json;
 /// Source lexer.
 pub mod lexer;
+/// IR optimization passes.
+pub mod optimize;
 /// Recursive descent parser.
 pub mod parser;
 /// Semantic analyzer.
 pub mod semantic;
 /// Built-in simulator and VCD emitter.
 pub mod simulator;
+/// Compile-and-simulate assertion helpers.
+#[cfg(feature = "testing")]
+pub mod testing;
 /// Verilog backend.
 pub mod verilog;
 
 use diagnostic::Result;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
 
 #[derive(Clone, Debug)]
 pub struct CompileOutput {
@@ -38,15 +47,70 @@ pub struct CompileOutput {
     pub ir: ir::IrModule,
 }
 
+/// Options for the compiler pipeline.
+///
+/// The defaults match the permissive behavior of [`compile`].
+#[derive(Clone, Debug, Default)]
+pub struct CompileOptions {
+    /// Require conditions to be one bit wide; see
+    /// [`semantic::AnalysisOptions::strict_conditions`].
+    pub strict_conditions: bool,
+    /// Feature names that enable matching `#if NAME` blocks.
+    pub defines: BTreeSet<String>,
+    /// Path of the file being compiled. `include` directives resolve relative
+    /// to its directory and are rejected when this is `None`.
+    pub source_path: Option<PathBuf>,
+    /// Run IR optimization passes after lowering; see
+    /// [`optimize::fold_constants`].
+    pub optimize: bool,
+}
+
 /// Run the full frontend and IR lowering pipeline for one Frag module.
 ///
 /// This function performs lexing, parsing, semantic analysis, and lowering.
 /// It does not emit Verilog or run simulation; those are separate backend
 /// steps that consume [`CompileOutput::ir`].
 pub fn compile(source: &str) -> Result<CompileOutput> {
-    let ast = parser::parse_source(source)?;
-    let analysis = semantic::analyze(&ast)?;
-    let ir = ir::lower(&ast, &analysis);
+    compile_with_options(source, &CompileOptions::default())
+}
+
+/// Run the full pipeline with non-default [`CompileOptions`].
+///
+/// Returns the first error; use [`parse_with_options`] to see every syntax
+/// error in the source.
+pub fn compile_with_options(source: &str, options: &CompileOptions) -> Result<CompileOutput> {
+    let ast = parse_with_options(source, options).map_err(|errors| {
+        errors
+            .into_iter()
+            .next()
+            .expect("failed parses report errors")
+    })?;
+    compile_ast(ast, options)
+}
+
+/// Parse source with [`CompileOptions`], reporting every syntax error.
+pub fn parse_with_options(
+    source: &str,
+    options: &CompileOptions,
+) -> std::result::Result<ast::Module, Vec<diagnostic::Diagnostic>> {
+    parser::parse_source_recovering(source, options.source_path.as_deref(), &options.defines)
+}
+
+/// Run semantic analysis and IR lowering on an already-built AST.
+///
+/// This is the entry point for tools that construct modules with
+/// [`ast::ModuleBuilder`] instead of generating source text.
+pub fn compile_ast(ast: ast::Module, options: &CompileOptions) -> Result<CompileOutput> {
+    let analysis = semantic::analyze_with_options(
+        &ast,
+        &semantic::AnalysisOptions {
+            strict_conditions: options.strict_conditions,
+        },
+    )?;
+    let mut ir = ir::lower(&ast, &analysis);
+    if options.optimize {
+        optimize::fold_constants(&mut ir);
+    }
     ir::validate(&ir)?;
     Ok(CompileOutput { ast, analysis, ir })
 }
@@ -5,15 +5,21 @@
 
 #![forbid(unsafe_code)]
 
-use frag_compiler::diagnostic::{Diagnostic, Result};
-use frag_compiler::lexer::{lex, TokenKind};
+use frag_compiler::ast::{self, DeclKind, Declaration, Type};
+use frag_compiler::diagnostic::{self, Diagnostic, Result, Span};
+use frag_compiler::lexer::{lex, lex_with_defines, TokenKind};
 use frag_compiler::simulator::{SimOptions, SimulationResult};
-use frag_compiler::{compile, graph, parser, simulator, verilog};
+use frag_compiler::{
+    compile_ast, graph, json, parser, semantic, simulator, verilog, CompileOptions,
+};
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::env;
 use std::fs;
+use std::io::{self, BufRead, Write};
 use std::path::Path;
 use std::process;
+use std::time::{Duration, Instant};
 
 fn main() {
     if let Err(error) = run_cli() {
@@ -28,6 +34,17 @@ fn run_cli() -> Result<()> {
         print_usage();
         return Ok(());
     }
+    if args[0] == "--explain" {
+        return command_explain(&args[1..]);
+    }
+    if args[0] == "repl" {
+        let (options, timed, rest) = compile_options(&args[1..])?;
+        if timed {
+            return Err(Diagnostic::new("Unknown option `--time`"));
+        }
+        no_options(&rest)?;
+        return command_repl(&options);
+    }
 
     let commands = ["tokens", "ast", "ir", "check", "verilog", "run", "graph"];
     let (command, file, rest) = if commands.contains(&args[0].as_str()) {
@@ -48,28 +65,285 @@ fn run_cli() -> Result<()> {
         )));
     };
 
+    let (mut options, timed, rest) = compile_options(rest)?;
+    options.source_path = Some(file.into());
+    let timer = Timer::new(timed);
     match command {
-        "tokens" => command_tokens(file),
-        "ast" => command_ast(file),
-        "ir" => command_ir(file),
-        "check" => command_check(file),
-        "verilog" => command_verilog(file, rest),
-        "run" => command_run(file, rest),
-        "graph" => command_graph(file, rest),
+        "tokens" => command_tokens(file, &options, &timer, &rest),
+        "ast" => command_ast(file, &options, &timer, &rest),
+        "ir" => command_ir(file, &options, &timer, &rest),
+        "check" => command_check(file, &options, &timer, &rest),
+        "verilog" => command_verilog(file, &options, &timer, &rest),
+        "run" => command_run(file, &options, &timer, &rest),
+        "graph" => command_graph(file, &options, &timer, &rest),
         _ => unreachable!(),
+    }?;
+    timer.report();
+    Ok(())
+}
+
+/// Wall-clock time spent in each compiler phase, collected for `--time`.
+struct Timer {
+    phases: Option<RefCell<Vec<(&'static str, Duration)>>>,
+}
+
+impl Timer {
+    fn new(enabled: bool) -> Self {
+        Self {
+            phases: enabled.then(|| RefCell::new(Vec::new())),
+        }
+    }
+
+    /// Run `work` and record its duration under `phase` when timing is on.
+    fn time<T>(&self, phase: &'static str, work: impl FnOnce() -> T) -> T {
+        let Some(phases) = &self.phases else {
+            return work();
+        };
+        let start = Instant::now();
+        let value = work();
+        phases.borrow_mut().push((phase, start.elapsed()));
+        value
+    }
+
+    /// Print each phase in microseconds to stderr, followed by the total.
+    fn report(&self) {
+        let Some(phases) = &self.phases else {
+            return;
+        };
+        let phases = phases.borrow();
+        let total = phases.iter().map(|(_, elapsed)| *elapsed).sum::<Duration>();
+        for (phase, elapsed) in phases.iter().chain([&("total", total)]) {
+            eprintln!("{:<9}{:>10} us", phase, elapsed.as_micros());
+        }
+    }
+}
+
+/// Split compiler-wide flags and `--time` from command-specific arguments.
+fn compile_options(args: &[String]) -> Result<(CompileOptions, bool, Vec<String>)> {
+    let mut options = CompileOptions::default();
+    let mut timed = false;
+    let mut rest = Vec::new();
+    let mut idx = 0;
+    while idx < args.len() {
+        match args[idx].as_str() {
+            "--strict" => options.strict_conditions = true,
+            "--time" => timed = true,
+            "-O" | "--optimize" => options.optimize = true,
+            "-D" | "--define" => {
+                let name = args.get(idx + 1).ok_or_else(|| {
+                    Diagnostic::new(format!("Missing feature name after `{}`", args[idx]))
+                })?;
+                options.defines.insert(name.clone());
+                idx += 1;
+            }
+            // Keep a command option's value with it, so `-o --time` names a file.
+            "-o" | "--output" | "--format" | "--ticks" | "--set" | "--vcd" | "--seed" => {
+                rest.extend(args[idx..].iter().take(2).cloned());
+                idx += 1;
+            }
+            _ => rest.push(args[idx].clone()),
+        }
+        idx += 1;
     }
+    Ok((options, timed, rest))
 }
 
-fn command_tokens(file: &str) -> Result<()> {
+fn command_explain(args: &[String]) -> Result<()> {
+    let [code] = args else {
+        return Err(Diagnostic::new("Usage: frag --explain <code>"));
+    };
+    let text = diagnostic::explain(code)
+        .ok_or_else(|| Diagnostic::new(format!("Unknown error code `{}`", code)))?;
+    println!("{}", text);
+    Ok(())
+}
+
+/// Name of the output that holds an evaluated REPL expression.
+const REPL_VALUE: &str = "__value";
+
+fn command_repl(options: &CompileOptions) -> Result<()> {
+    let mut consts = Vec::new();
+    let mut line = String::new();
+    let mut failed = false;
+    loop {
+        print!("frag> ");
+        io::stdout().flush().ok();
+        line.clear();
+        let read = io::stdin()
+            .lock()
+            .read_line(&mut line)
+            .map_err(|error| Diagnostic::new(format!("Failed to read input: {}", error)))?;
+        let input = line.trim();
+        if read == 0 || input == ":quit" || input == ":q" {
+            println!();
+            // Piped input that ends with errors fails, so scripts notice.
+            if read == 0 && failed {
+                return Err(Diagnostic::new("REPL input contained errors"));
+            }
+            return Ok(());
+        }
+        if input.is_empty() {
+            continue;
+        }
+        match repl_eval(&mut consts, input, options) {
+            Ok(Some(value)) => println!("{}", value),
+            Ok(None) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+                failed = true;
+            }
+        }
+    }
+}
+
+/// Evaluate one REPL line: a `const` declaration to remember, or a constant
+/// expression whose value and width are returned.
+fn repl_eval(
+    consts: &mut Vec<String>,
+    input: &str,
+    options: &CompileOptions,
+) -> Result<Option<String>> {
+    let kinds = lex(input)
+        .map(|tokens| {
+            tokens
+                .into_iter()
+                .map(|token| token.kind)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let is_const = kinds.first() == Some(&TokenKind::Const);
+    // Expressions may end with `;`. A missing one is added on its own line,
+    // so a trailing `//` comment cannot swallow it.
+    let terminated = kinds.len() >= 2 && kinds[kinds.len() - 2] == TokenKind::Semicolon;
+    let (lead, tail) = match (is_const, terminated) {
+        (true, _) => (String::new(), ""),
+        (false, true) => (format!("{} = ", REPL_VALUE), ""),
+        (false, false) => (format!("{} = ", REPL_VALUE), "\n;"),
+    };
+    let prefix = format!("module Repl {{\n{}\n", consts.join("\n"));
+    let source = format!("{}{}{}{}\n}}\n", prefix, lead, input, tail);
+    let start = prefix.len() + lead.len();
+    let in_input = |error: Diagnostic| match error.span {
+        Some(span) if span.start >= start && span.end <= start + input.len() => {
+            let span = Span::new(span.start - start, span.end - start);
+            Diagnostic::new(
+                Diagnostic {
+                    span: Some(span),
+                    ..error
+                }
+                .with_source(input),
+            )
+        }
+        _ => error,
+    };
+
+    let mut ast = parser::parse_source_with_defines(&source, &options.defines).map_err(in_input)?;
+    let known = parser::parse_source_with_defines(&format!("{}}}\n", prefix), &options.defines)?;
+    // A `;` inside the line could end the wrapper early and add more items,
+    // such as a process, so the line must add exactly what the wrapper does.
+    let single = if is_const {
+        ast.declarations.len() == known.declarations.len() + 1
+            && ast.assignments.is_empty()
+            && ast.declarations.last().map(|decl| decl.kind) == Some(DeclKind::Const)
+    } else {
+        ast.declarations.len() == known.declarations.len() && ast.assignments.len() == 1
+    };
+    if !single || !ast.processes.is_empty() {
+        return Err(Diagnostic::new(
+            "The REPL evaluates one expression or `const` declaration per line",
+        ));
+    }
+    if is_const {
+        compile_ast(ast, options).map_err(in_input)?;
+        consts.push(input.to_string());
+        return Ok(None);
+    }
+    let symbols = semantic::analyze(&known)?.symbols;
+    let value = ast
+        .assignments
+        .iter()
+        .find(|assignment| assignment.target == REPL_VALUE)
+        .expect("the REPL wraps expressions in an assignment");
+    let width = semantic::expr_width(&value.expr, &symbols);
+    ast.declarations.push(Declaration {
+        kind: DeclKind::Output,
+        name: REPL_VALUE.to_string(),
+        ty: Type { width },
+        value: None,
+        span: Span::default(),
+    });
+    let output = compile_ast(ast, options).map_err(in_input)?;
+    let SimulationResult::TruthTable(table) = simulator::run(&output.ir, &SimOptions::default())?
+    else {
+        return Err(Diagnostic::new(
+            "The REPL evaluates combinational expressions only",
+        ));
+    };
+    let ty = if width == 1 {
+        "bit".to_string()
+    } else {
+        format!("u{}", width)
+    };
+    Ok(Some(format!("{} : {}", table.rows[0][REPL_VALUE], ty)))
+}
+
+fn compile_file(
+    file: &str,
+    source: &str,
+    options: &CompileOptions,
+    timer: &Timer,
+) -> Result<frag_compiler::CompileOutput> {
+    let ast = parse_file(file, source, options, timer)?;
+    timer
+        .time("analyze", || compile_ast(ast, options))
+        .map_err(|error| with_file(error, file, source))
+}
+
+fn parse_file(
+    file: &str,
+    source: &str,
+    options: &CompileOptions,
+    timer: &Timer,
+) -> Result<ast::Module> {
+    let tokens = timer
+        .time("lex", || lex_with_defines(source, &options.defines))
+        .map_err(|error| with_file(error, file, source))?;
+    timer
+        .time("parse", || {
+            parser::parse_tokens_recovering(
+                tokens,
+                options.source_path.as_deref(),
+                &options.defines,
+            )
+        })
+        .map_err(|errors| {
+            let rendered = errors
+                .iter()
+                .map(|error| error.with_source(source))
+                .collect::<Vec<_>>();
+            Diagnostic::new(format!("{}:\n{}", file, rendered.join("\n\n")))
+        })
+}
+
+fn command_tokens(
+    file: &str,
+    options: &CompileOptions,
+    timer: &Timer,
+    args: &[String],
+) -> Result<()> {
+    no_options(args)?;
     let source = read_source(file)?;
-    let tokens = lex(&source).map_err(|error| with_file(error, file, &source))?;
+    let tokens = timer
+        .time("lex", || lex_with_defines(&source, &options.defines))
+        .map_err(|error| with_file(error, file, &source))?;
     for token in tokens {
         if matches!(token.kind, TokenKind::Eof) {
             continue;
         }
         println!(
-            "{} @ {}..{}",
+            "{} @ {} ({}..{})",
             token_label(&token.kind),
+            token.span.location(&source),
             token.span.start,
             token.span.end
         );
@@ -77,31 +351,52 @@ fn command_tokens(file: &str) -> Result<()> {
     Ok(())
 }
 
-fn command_ast(file: &str) -> Result<()> {
+fn command_ast(file: &str, options: &CompileOptions, timer: &Timer, args: &[String]) -> Result<()> {
+    let json = match args {
+        [] => false,
+        [flag] if flag == "--json" => true,
+        [other, ..] => return Err(Diagnostic::new(format!("Unknown option `{}`", other))),
+    };
     let source = read_source(file)?;
-    let ast = parser::parse_source(&source).map_err(|error| with_file(error, file, &source))?;
-    println!("{:#?}", ast);
+    let ast = parse_file(file, &source, options, timer)?;
+    if json {
+        println!("{}", json::module_to_json(&ast));
+    } else {
+        println!("{:#?}", ast);
+    }
     Ok(())
 }
 
-fn command_ir(file: &str) -> Result<()> {
+fn command_ir(file: &str, options: &CompileOptions, timer: &Timer, args: &[String]) -> Result<()> {
+    no_options(args)?;
     let source = read_source(file)?;
-    let output = compile(&source).map_err(|error| with_file(error, file, &source))?;
+    let output = compile_file(file, &source, options, timer)?;
     println!("{}", output.ir);
     Ok(())
 }
 
-fn command_check(file: &str) -> Result<()> {
+fn command_check(
+    file: &str,
+    options: &CompileOptions,
+    timer: &Timer,
+    args: &[String],
+) -> Result<()> {
+    no_options(args)?;
     let source = read_source(file)?;
-    let output = compile(&source).map_err(|error| with_file(error, file, &source))?;
+    let output = compile_file(file, &source, options, timer)?;
     println!("OK {}", output.ir.name);
     Ok(())
 }
 
-fn command_verilog(file: &str, args: &[String]) -> Result<()> {
+fn command_verilog(
+    file: &str,
+    options: &CompileOptions,
+    timer: &Timer,
+    args: &[String],
+) -> Result<()> {
     let source = read_source(file)?;
-    let output = compile(&source).map_err(|error| with_file(error, file, &source))?;
-    let verilog = verilog::emit(&output.ir);
+    let output = compile_file(file, &source, options, timer)?;
+    let verilog = timer.time("emit", || verilog::emit(&output.ir));
     if let Some(path) = output_path(args)? {
         fs::write(&path, verilog).map_err(|error| {
             Diagnostic::new(format!("Failed to write `{}`: {}", path.display(), error))
@@ -112,11 +407,11 @@ fn command_verilog(file: &str, args: &[String]) -> Result<()> {
     Ok(())
 }
 
-fn command_run(file: &str, args: &[String]) -> Result<()> {
+fn command_run(file: &str, options: &CompileOptions, timer: &Timer, args: &[String]) -> Result<()> {
     let source = read_source(file)?;
-    let output = compile(&source).map_err(|error| with_file(error, file, &source))?;
+    let output = compile_file(file, &source, options, timer)?;
     let (options, vcd_path) = run_options(args)?;
-    let result = simulator::run(&output.ir, &options)?;
+    let result = timer.time("simulate", || simulator::run(&output.ir, &options))?;
     print!("{}", result);
 
     if let Some(path) = vcd_path {
@@ -137,13 +432,18 @@ fn command_run(file: &str, args: &[String]) -> Result<()> {
     Ok(())
 }
 
-fn command_graph(file: &str, args: &[String]) -> Result<()> {
+fn command_graph(
+    file: &str,
+    options: &CompileOptions,
+    timer: &Timer,
+    args: &[String],
+) -> Result<()> {
     let source = read_source(file)?;
-    let output = compile(&source).map_err(|error| with_file(error, file, &source))?;
+    let output = compile_file(file, &source, options, timer)?;
     let (format, path) = graph_options(args)?;
     let text = match format.as_str() {
-        "dot" => graph::emit_dot(&output.ir),
-        "mermaid" => graph::emit_mermaid(&output.ir),
+        "dot" => timer.time("emit", || graph::emit_dot(&output.ir)),
+        "mermaid" => timer.time("emit", || graph::emit_mermaid(&output.ir)),
         _ => {
             return Err(Diagnostic::new(format!(
                 "Unknown graph format `{}`; expected `dot` or `mermaid`",
@@ -162,6 +462,13 @@ fn command_graph(file: &str, args: &[String]) -> Result<()> {
     Ok(())
 }
 
+fn no_options(args: &[String]) -> Result<()> {
+    match args.first() {
+        Some(other) => Err(Diagnostic::new(format!("Unknown option `{}`", other))),
+        None => Ok(()),
+    }
+}
+
 fn output_path(args: &[String]) -> Result<Option<std::path::PathBuf>> {
     let mut idx = 0;
     let mut path = None;
@@ -211,6 +518,18 @@ fn run_options(args: &[String]) -> Result<(SimOptions, Option<std::path::PathBuf
                 };
                 vcd_path = Some(Path::new(value).to_path_buf());
             }
+            "--seed" => {
+                idx += 1;
+                let Some(value) = args.get(idx) else {
+                    return Err(Diagnostic::new("Missing number after `--seed`"));
+                };
+                options.seed = Some(
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| Diagnostic::new(format!("Invalid seed `{}`", value)))?,
+                );
+            }
+            "--checked" => options.checked = true,
             other => return Err(Diagnostic::new(format!("Unknown option `{}`", other))),
         }
         idx += 1;
@@ -285,6 +604,7 @@ fn token_label(kind: &TokenKind) -> String {
     match kind {
         TokenKind::Identifier(name) => format!("Identifier({})", name),
         TokenKind::Number(value) => format!("Number({})", value),
+        TokenKind::Str(text) => format!("Str({:?})", text),
         TokenKind::BoolLiteral(value) => format!("Bool({})", value),
         TokenKind::Module => "Module".to_string(),
         TokenKind::Input => "Input".to_string(),
@@ -298,8 +618,10 @@ fn token_label(kind: &TokenKind) -> String {
         TokenKind::If => "If".to_string(),
         TokenKind::Else => "Else".to_string(),
         TokenKind::Case => "Case".to_string(),
+        TokenKind::Include => "Include".to_string(),
         TokenKind::Bit => "Bit".to_string(),
         TokenKind::BoolType => "BoolType".to_string(),
+        TokenKind::Question => "Question".to_string(),
         TokenKind::Colon => "Colon".to_string(),
         TokenKind::Semicolon => "Semicolon".to_string(),
         TokenKind::Comma => "Comma".to_string(),
@@ -310,10 +632,16 @@ fn token_label(kind: &TokenKind) -> String {
         TokenKind::LeftBracket => "LeftBracket".to_string(),
         TokenKind::RightBracket => "RightBracket".to_string(),
         TokenKind::Equal => "Equal".to_string(),
+        TokenKind::PlusEqual => "PlusEqual".to_string(),
+        TokenKind::MinusEqual => "MinusEqual".to_string(),
+        TokenKind::StarEqual => "StarEqual".to_string(),
+        TokenKind::SlashEqual => "SlashEqual".to_string(),
+        TokenKind::PercentEqual => "PercentEqual".to_string(),
         TokenKind::FatArrow => "FatArrow".to_string(),
         TokenKind::Plus => "Plus".to_string(),
         TokenKind::Minus => "Minus".to_string(),
         TokenKind::Star => "Star".to_string(),
+        TokenKind::StarStar => "StarStar".to_string(),
         TokenKind::Slash => "Slash".to_string(),
         TokenKind::Percent => "Percent".to_string(),
         TokenKind::Amp => "And".to_string(),
@@ -340,11 +668,19 @@ fn print_usage() {
         "Usage:
   frag <file.frag>                  Generate Verilog
   frag tokens <file.frag>           Print tokens
-  frag ast <file.frag>              Print AST
+  frag ast <file.frag> [--json]     Print AST
   frag ir <file.frag>               Print netlist IR
   frag check <file.frag>            Validate frontend, semantics, and IR
   frag verilog <file.frag> [-o out] Generate Verilog
-  frag run <file.frag> [--ticks N] [--set a=1,b=0] [--vcd out.vcd]
-  frag graph <file.frag> [--format dot|mermaid] [-o out]"
+  frag run <file.frag> [--ticks N] [--set a=1,b=0] [--seed N] [--checked] [--vcd out.vcd]
+  frag graph <file.frag> [--format dot|mermaid] [-o out]
+  frag repl                         Evaluate constant expressions interactively
+  frag --explain <code>             Explain an error code such as E0001
+
+Compiler options:
+  --strict                          Require one-bit conditions (not tokens, ast)
+  -O, --optimize                    Fold constant expressions in the IR
+  -D, --define NAME                 Enable `#if NAME` blocks
+  --time                            Print microseconds spent in each phase (not repl)"
     );
 }
@@ -0,0 +1,98 @@
+//! IR optimization passes.
+//!
+//! Passes rewrite a validated [`IrModule`] in place and preserve simulation
+//! results exactly, including checked overflow reports, so they can run
+//! between lowering and any backend.
+
+use crate::ir::{IrExpr, IrModule};
+use crate::simulator;
+use std::collections::BTreeMap;
+
+/// Replace every expression whose operands are all constants with its value.
+///
+/// References to `const` declarations are inlined as their values, so a
+/// constant costs nothing at its use sites; the declarations themselves are
+/// kept for readability of the generated Verilog. Folding evaluates with the
+/// simulator, so widths and division by zero behave exactly as they would at
+/// run time, both for literal-only source expressions such as `~5` and for
+/// expressions made constant by inlining. Arithmetic that would wrap is left
+/// in place so checked simulation still reports it.
+pub fn fold_constants(module: &mut IrModule) {
+    let mut constants = BTreeMap::new();
+    for constant in &mut module.constants {
+        // Constants are in dependency order, so each one folds completely.
+        if fold_expr(&mut constant.expr, &constants) {
+            if let IrExpr::Const { value, .. } = constant.expr {
+                let value = simulator::mask(value, constant.width);
+                constants.insert(constant.name.clone(), value);
+            }
+        }
+    }
+
+    let exprs = module
+        .combinational
+        .iter_mut()
+        .map(|assignment| &mut assignment.expr)
+        .chain(
+            module
+                .processes
+                .iter_mut()
+                .flat_map(|process| &mut process.assignments)
+                .map(|assignment| &mut assignment.expr),
+        );
+    for expr in exprs {
+        fold_expr(expr, &constants);
+    }
+}
+
+/// Fold `expr` bottom-up and report whether it is now a constant.
+fn fold_expr(expr: &mut IrExpr, constants: &BTreeMap<String, u128>) -> bool {
+    let foldable = match expr {
+        IrExpr::Const { .. } => return true,
+        IrExpr::Signal { name, width } => match constants.get(name) {
+            Some(value) => {
+                *expr = IrExpr::Const {
+                    value: *value,
+                    width: *width,
+                };
+                return true;
+            }
+            None => return false,
+        },
+        IrExpr::Slice { expr, .. } | IrExpr::Unary { expr, .. } => fold_expr(expr, constants),
+        IrExpr::Binary { left, right, .. } => {
+            fold_expr(left, constants) & fold_expr(right, constants)
+        }
+        IrExpr::Mux {
+            select,
+            when_true,
+            when_false,
+            ..
+        } => {
+            fold_expr(select, constants)
+                & fold_expr(when_true, constants)
+                & fold_expr(when_false, constants)
+        }
+        IrExpr::Case { selector, arms, .. } => {
+            let mut foldable = fold_expr(selector, constants);
+            for arm in arms {
+                if let Some(pattern) = &mut arm.pattern {
+                    foldable &= fold_expr(pattern, constants);
+                }
+                foldable &= fold_expr(&mut arm.value, constants);
+            }
+            foldable
+        }
+    };
+    // Operands are already folded, so only this operator can overflow.
+    if foldable && simulator::find_overflow(expr, &BTreeMap::new()).is_some() {
+        return false;
+    }
+    if foldable {
+        *expr = IrExpr::Const {
+            value: simulator::eval_expr(expr, &BTreeMap::new()),
+            width: expr.width(),
+        };
+    }
+    foldable
+}
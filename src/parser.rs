@@ -5,12 +5,15 @@
 //! analysis.
 
 use crate::ast::{
-    Assignment, BinaryOp, CaseArm, DeclKind, Declaration, Edge, Expr, Module, Process, Type,
-    UnaryOp,
+    Assignment, BinaryOp, CaseArm, DeclKind, Declaration, Edge, Expr, ItemKind, Module, Process,
+    Type, UnaryOp,
 };
 use crate::diagnostic::{Diagnostic, Result, Span};
-use crate::lexer::{lex, Token, TokenKind};
+use crate::lexer::{lex, lex_with_defines, Token, TokenKind};
+use std::collections::BTreeSet;
+use std::fs;
 use std::mem::discriminant;
+use std::path::{Path, PathBuf};
 
 /// Parse one source string into a Frag module AST.
 pub fn parse_source(source: &str) -> Result<Module> {
@@ -18,16 +21,154 @@ pub fn parse_source(source: &str) -> Result<Module> {
     Parser::new(tokens).parse_module()
 }
 
+/// Parse one source string with the given `#if` features defined.
+pub fn parse_source_with_defines(source: &str, defines: &BTreeSet<String>) -> Result<Module> {
+    let tokens = lex_with_defines(source, defines)?;
+    Parser::new(tokens).parse_module()
+}
+
+/// Parse source read from `path`, resolving `include` directives.
+///
+/// Included paths are relative to the directory of the including file.
+/// Sources parsed without a path reject `include`.
+pub fn parse_source_at(source: &str, path: &Path, defines: &BTreeSet<String>) -> Result<Module> {
+    let tokens = lex_with_defines(source, defines)?;
+    let mut parser = Parser::new(tokens);
+    parser.includes = Some(Includes::root(path, defines));
+    parser.parse_module()
+}
+
+/// Parse source and report every syntax error instead of only the first.
+///
+/// After an error the parser skips to the end of the failed item or process
+/// statement and continues. `path` enables `include` as in
+/// [`parse_source_at`]. Lexer errors still stop at the first one.
+pub fn parse_source_recovering(
+    source: &str,
+    path: Option<&Path>,
+    defines: &BTreeSet<String>,
+) -> std::result::Result<Module, Vec<Diagnostic>> {
+    let tokens = lex_with_defines(source, defines).map_err(|error| vec![error])?;
+    parse_tokens_recovering(tokens, path, defines)
+}
+
+/// Parse already-lexed tokens like [`parse_source_recovering`].
+///
+/// `defines` is only used to lex included files.
+pub fn parse_tokens_recovering(
+    tokens: Vec<Token>,
+    path: Option<&Path>,
+    defines: &BTreeSet<String>,
+) -> std::result::Result<Module, Vec<Diagnostic>> {
+    let mut parser = Parser::new(tokens);
+    parser.includes = path.map(|path| Includes::root(path, defines));
+    parser.parse_module_recovering()
+}
+
+/// Built-in functions, each taking two arguments and parsed as a binary
+/// operator.
+#[derive(Clone, Copy)]
+enum Builtin {
+    Min,
+    Max,
+    Pow,
+}
+
+impl Builtin {
+    const ALL: [Builtin; 3] = [Builtin::Min, Builtin::Max, Builtin::Pow];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|builtin| builtin.name() == name)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Builtin::Min => "min",
+            Builtin::Max => "max",
+            Builtin::Pow => "pow",
+        }
+    }
+
+    fn op(self) -> BinaryOp {
+        match self {
+            Builtin::Min => BinaryOp::Min,
+            Builtin::Max => BinaryOp::Max,
+            Builtin::Pow => BinaryOp::Pow,
+        }
+    }
+
+    /// The built-in names as an English list, such as "`min`, `max`, and `pow`".
+    fn listed() -> String {
+        let names = Self::ALL.map(|builtin| format!("`{}`", builtin.name()));
+        let (last, rest) = names.split_last().expect("there are built-in functions");
+        format!("{}, and {}", rest.join(", "), last)
+    }
+}
+
 /// Parser state over a token vector.
 pub struct Parser {
     tokens: Vec<Token>,
     pos: usize,
+    open_parens: Vec<Span>,
+    includes: Option<Includes>,
+    /// Errors recorded so far when recovering; see [`Parser::parse_module_recovering`].
+    errors: Option<Vec<Diagnostic>>,
+}
+
+/// Parser position and open parentheses to restore after a recovered error.
+#[derive(Clone, Copy)]
+struct Checkpoint {
+    pos: usize,
+    open_parens: usize,
+}
+
+/// File context for resolving `include` directives.
+struct Includes {
+    /// Directory that relative include paths resolve against.
+    base_dir: PathBuf,
+    /// Canonical paths of the files being parsed, outermost first.
+    stack: Vec<PathBuf>,
+    defines: BTreeSet<String>,
+}
+
+impl Includes {
+    fn root(path: &Path, defines: &BTreeSet<String>) -> Self {
+        let file = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        Self {
+            base_dir: file.parent().map(Path::to_path_buf).unwrap_or_default(),
+            stack: vec![file],
+            defines: defines.clone(),
+        }
+    }
 }
 
 impl Parser {
     /// Create a parser from tokens.
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, pos: 0 }
+        Self {
+            tokens,
+            pos: 0,
+            open_parens: Vec::new(),
+            includes: None,
+            errors: None,
+        }
+    }
+
+    /// Parse one module, recovering after syntax errors to report them all.
+    ///
+    /// Returns the module only when there were no errors.
+    pub fn parse_module_recovering(&mut self) -> std::result::Result<Module, Vec<Diagnostic>> {
+        self.errors = Some(Vec::new());
+        let result = self.parse_module();
+        let mut errors = self.errors.take().unwrap_or_default();
+        match result {
+            Ok(module) if errors.is_empty() => Ok(module),
+            Ok(_) => Err(errors),
+            Err(error) => {
+                errors.push(error);
+                Err(errors)
+            }
+        }
     }
 
     /// Parse the complete token stream as one module.
@@ -39,40 +180,164 @@ impl Parser {
         let name = self.expect_identifier()?;
         self.expect_simple(TokenKind::LeftBrace, "`{`")?;
 
-        let mut declarations = Vec::new();
-        let mut assignments = Vec::new();
-        let mut processes = Vec::new();
-
+        let mut module = Module {
+            name,
+            declarations: Vec::new(),
+            assignments: Vec::new(),
+            processes: Vec::new(),
+            order: Vec::new(),
+            span: Span::default(),
+        };
         while !self.at_simple(&TokenKind::RightBrace) && !self.at_simple(&TokenKind::Eof) {
-            match &self.peek().kind {
-                TokenKind::Input | TokenKind::Output | TokenKind::Wire | TokenKind::Reg => {
-                    declarations.push(self.parse_declaration()?);
-                }
-                TokenKind::Const => declarations.push(self.parse_const_declaration()?),
-                TokenKind::On => processes.push(self.parse_process()?),
-                TokenKind::Identifier(_) => assignments.push(self.parse_assignment()?),
-                token => {
-                    return Err(Diagnostic::at(
-                        self.peek().span,
-                        format!(
-                            "Expected declaration, assignment, or process, found {}",
-                            token
-                        ),
-                    ));
-                }
+            let checkpoint = self.checkpoint();
+            if let Err(error) = self.parse_item(&mut module) {
+                self.recover(error, checkpoint)?;
             }
         }
 
         let end = self.expect_simple(TokenKind::RightBrace, "`}`")?.span.end;
         self.expect_simple(TokenKind::Eof, "end of file")?;
+        module.span = Span::new(start, end);
+        Ok(module)
+    }
 
-        Ok(Module {
-            name,
-            declarations,
-            assignments,
-            processes,
-            span: Span::new(start, end),
-        })
+    /// Parse one module item and add it to `module`.
+    fn parse_item(&mut self, module: &mut Module) -> Result<()> {
+        match &self.peek().kind {
+            TokenKind::Input | TokenKind::Output | TokenKind::Wire | TokenKind::Reg => {
+                module.declarations.push(self.parse_declaration()?);
+                module.order.push(ItemKind::Declaration);
+            }
+            TokenKind::Const => {
+                module.declarations.push(self.parse_const_declaration()?);
+                module.order.push(ItemKind::Declaration);
+            }
+            TokenKind::On => {
+                module.processes.push(self.parse_process()?);
+                module.order.push(ItemKind::Process);
+            }
+            TokenKind::Include => self.parse_include(module)?,
+            TokenKind::Identifier(_) => {
+                module.assignments.push(self.parse_assignment()?);
+                module.order.push(ItemKind::Assignment);
+            }
+            _ if self.at_keyword_assignment() => return Err(self.reserved_keyword()),
+            token => {
+                return Err(Diagnostic::at(
+                    self.peek().span,
+                    format!(
+                        "Expected declaration, assignment, or process, found {}",
+                        token
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse `include "path";` and splice the file's items into `module`.
+    ///
+    /// Included items are re-spanned to the directive, since their own spans
+    /// point into another file; later diagnostics about them are reported at
+    /// the `include` line. They keep their order through [`Module::order`].
+    fn parse_include(&mut self, module: &mut Module) -> Result<()> {
+        let start = self.bump().span.start;
+        let token = self.bump();
+        let TokenKind::Str(path) = token.kind else {
+            return Err(Diagnostic::at(
+                token.span,
+                format!(
+                    "Expected file path string after `include`, found {}",
+                    token.kind
+                ),
+            ));
+        };
+        let end = self.expect_simple(TokenKind::Semicolon, "`;`")?.span.end;
+        let span = Span::new(start, end);
+
+        let Some(includes) = &self.includes else {
+            return Err(Diagnostic::at(
+                span,
+                "`include` requires the path of the including file to resolve against",
+            ));
+        };
+        let unreadable = |error: std::io::Error| {
+            Diagnostic::at(
+                span,
+                format!("Cannot read included file `{}`: {}", path, error),
+            )
+        };
+        let file = fs::canonicalize(includes.base_dir.join(&path)).map_err(unreadable)?;
+        if let Some(first) = includes.stack.iter().position(|open| *open == file) {
+            let cycle = includes.stack[first..]
+                .iter()
+                .chain([&file])
+                .map(|open| file_label(open))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(Diagnostic::at(span, format!("Circular include: {}", cycle)));
+        }
+        let source = fs::read_to_string(&file).map_err(unreadable)?;
+
+        let in_file = |error: Diagnostic| Diagnostic {
+            code: error.code,
+            ..Diagnostic::at(
+                span,
+                format!(
+                    "In included file `{}`:\n{}",
+                    path,
+                    Diagnostic {
+                        code: None,
+                        ..error
+                    }
+                    .with_source(&source)
+                ),
+            )
+        };
+        let tokens = lex_with_defines(&source, &includes.defines).map_err(in_file)?;
+        let mut parser = Parser::new(tokens);
+        parser.includes = Some(Includes {
+            base_dir: file.parent().map(Path::to_path_buf).unwrap_or_default(),
+            stack: includes
+                .stack
+                .iter()
+                .cloned()
+                .chain([file.clone()])
+                .collect(),
+            defines: includes.defines.clone(),
+        });
+        let mut included = Module {
+            name: String::new(),
+            declarations: Vec::new(),
+            assignments: Vec::new(),
+            processes: Vec::new(),
+            order: Vec::new(),
+            span,
+        };
+        while !parser.at_simple(&TokenKind::Eof) {
+            parser.parse_item(&mut included).map_err(in_file)?;
+        }
+
+        for mut declaration in included.declarations {
+            declaration.span = span;
+            if let Some(value) = &mut declaration.value {
+                respan(value, span);
+            }
+            module.declarations.push(declaration);
+        }
+        for mut assignment in included.assignments {
+            respan_assignment(&mut assignment, span);
+            module.assignments.push(assignment);
+        }
+        for mut process in included.processes {
+            process.span = span;
+            process.assignments.iter_mut().for_each(|assignment| {
+                respan_assignment(assignment, span);
+            });
+            module.processes.push(process);
+        }
+        module.order.extend(included.order);
+        Ok(())
     }
 
     fn parse_declaration(&mut self) -> Result<Declaration> {
@@ -164,30 +429,180 @@ impl Parser {
         self.expect_simple(TokenKind::LeftParen, "`(`")?;
         let clock = self.expect_identifier()?;
         self.expect_simple(TokenKind::RightParen, "`)`")?;
-        self.expect_simple(TokenKind::LeftBrace, "`{`")?;
-
-        let mut assignments = Vec::new();
-        while !self.at_simple(&TokenKind::RightBrace) && !self.at_simple(&TokenKind::Eof) {
-            assignments.push(self.parse_assignment()?);
-        }
-        let end = self.expect_simple(TokenKind::RightBrace, "`}`")?.span.end;
+        let (assignments, body) = self.parse_statement_block()?;
 
         Ok(Process {
             edge,
             clock,
             assignments,
-            span: Span::new(start, end),
+            span: Span::new(start, body.end),
         })
     }
 
+    /// Parse a braced block of process statements and its span.
+    ///
+    /// `if` statements are desugared here, so a block always yields plain
+    /// assignments.
+    fn parse_statement_block(&mut self) -> Result<(Vec<Assignment>, Span)> {
+        let start = self.expect_simple(TokenKind::LeftBrace, "`{`")?.span.start;
+        let mut assignments = Vec::new();
+        while !self.at_simple(&TokenKind::RightBrace) && !self.at_simple(&TokenKind::Eof) {
+            let checkpoint = self.checkpoint();
+            if let Err(error) = self.parse_statement(&mut assignments) {
+                self.recover(error, checkpoint)?;
+            }
+        }
+        let end = self.expect_simple(TokenKind::RightBrace, "`}`")?.span.end;
+        Ok((assignments, Span::new(start, end)))
+    }
+
+    fn parse_statement(&mut self, assignments: &mut Vec<Assignment>) -> Result<()> {
+        match self.peek().kind {
+            TokenKind::Identifier(_) => assignments.push(self.parse_assignment()?),
+            TokenKind::If => assignments.extend(self.parse_if_statement()?),
+            _ if self.at_keyword_assignment() => return Err(self.reserved_keyword()),
+            ref other => {
+                return Err(Diagnostic::at(
+                    self.peek().span,
+                    format!("Expected assignment or `if`, found {}", other),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            pos: self.pos,
+            open_parens: self.open_parens.len(),
+        }
+    }
+
+    /// Record `error` and skip past the item or statement that started at
+    /// `checkpoint`, or return the error when not recovering.
+    fn recover(&mut self, error: Diagnostic, checkpoint: Checkpoint) -> Result<()> {
+        let Some(errors) = &mut self.errors else {
+            return Err(error);
+        };
+        errors.push(error);
+        self.pos = checkpoint.pos;
+        self.open_parens.truncate(checkpoint.open_parens);
+        self.synchronize();
+        Ok(())
+    }
+
+    /// Skip one item or statement: through the next `;` outside braces,
+    /// stopping early at a `}` that closes the enclosing block or at a keyword
+    /// that starts a new item.
+    ///
+    /// Blocks inside an expression, such as the branches of an `if`, are
+    /// followed by `else`, an operator, or the `;`, so skipping continues
+    /// past them. A block followed by the next statement, such as a process
+    /// body, ends the skip.
+    fn synchronize(&mut self) {
+        let start = self.pos;
+        let mut depth = 0usize;
+        loop {
+            match self.peek().kind {
+                TokenKind::Eof => return,
+                TokenKind::RightBrace if depth == 0 => return,
+                TokenKind::Input
+                | TokenKind::Output
+                | TokenKind::Wire
+                | TokenKind::Reg
+                | TokenKind::Const
+                | TokenKind::On
+                | TokenKind::Include
+                    if depth == 0 && self.pos > start =>
+                {
+                    return
+                }
+                TokenKind::LeftBrace => depth += 1,
+                TokenKind::RightBrace => {
+                    depth -= 1;
+                    self.pos += 1;
+                    if depth == 0
+                        && matches!(self.peek().kind, TokenKind::Identifier(_) | TokenKind::If)
+                    {
+                        return;
+                    }
+                    continue;
+                }
+                TokenKind::Semicolon if depth == 0 => {
+                    self.pos += 1;
+                    return;
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Parse an `if` statement and desugar it into conditional assignments.
+    ///
+    /// Each register assigned in either branch gets one assignment selecting
+    /// between the branches, and a branch that does not assign the register
+    /// keeps its current value. The `else` branch is optional, and an
+    /// `else if` chain is parsed in a loop and merged from its last arm out.
+    fn parse_if_statement(&mut self) -> Result<Vec<Assignment>> {
+        let mut start = self.expect_simple(TokenKind::If, "`if`")?.span.start;
+        let mut arms = Vec::new();
+        let (mut merged, end) = loop {
+            let condition = self.parse_expr()?;
+            let (then_assignments, then_block) = self.parse_statement_block()?;
+            arms.push((start, condition, then_assignments));
+            if !self.match_simple(&TokenKind::Else) {
+                break (Vec::new(), then_block.end);
+            }
+            if !self.at_simple(&TokenKind::If) {
+                let (assignments, block) = self.parse_statement_block()?;
+                break (assignments, block.end);
+            }
+            start = self.bump().span.start;
+        };
+
+        for (start, condition, then_assignments) in arms.into_iter().rev() {
+            let span = Span::new(start, end);
+            let mut else_assignments = merged.into_iter().map(Some).collect::<Vec<_>>();
+            merged = Vec::new();
+            for then_assignment in then_assignments {
+                let else_expr = else_assignments
+                    .iter_mut()
+                    .find(|slot| {
+                        slot.as_ref()
+                            .is_some_and(|other| other.target == then_assignment.target)
+                    })
+                    .and_then(Option::take)
+                    .map(|assignment| assignment.expr);
+                merged.push(select(&condition, span, then_assignment, else_expr, true));
+            }
+            for else_assignment in else_assignments.into_iter().flatten() {
+                merged.push(select(&condition, span, else_assignment, None, false));
+            }
+        }
+        Ok(merged)
+    }
+
     fn parse_assignment(&mut self) -> Result<Assignment> {
         let target_token = self.bump();
         let (target, start) = match target_token.kind {
             TokenKind::Identifier(name) => (name, target_token.span.start),
             _ => unreachable!(),
         };
-        self.expect_simple(TokenKind::Equal, "`=`")?;
-        let expr = self.parse_expr()?;
+        let compound = compound_assignment_op(&self.peek().kind);
+        if compound.is_some() {
+            self.bump();
+        } else {
+            self.expect_simple(TokenKind::Equal, "`=`")?;
+        }
+        let mut expr = self.parse_expr()?;
+        if let Some(op) = compound {
+            let current = Expr::Signal {
+                name: target.clone(),
+                span: target_token.span,
+            };
+            expr = binary(op, current, expr);
+        }
         let end = self.expect_simple(TokenKind::Semicolon, "`;`")?.span.end;
         Ok(Assignment {
             target,
@@ -197,7 +612,20 @@ impl Parser {
     }
 
     fn parse_expr(&mut self) -> Result<Expr> {
-        self.parse_logic_or()
+        let condition = self.parse_logic_or()?;
+        if !self.match_simple(&TokenKind::Question) {
+            return Ok(condition);
+        }
+        let then_expr = self.parse_expr()?;
+        self.expect_simple(TokenKind::Colon, "`:`")?;
+        let else_expr = self.parse_expr()?;
+        let span = Span::new(condition.span().start, else_expr.span().end);
+        Ok(Expr::Conditional {
+            condition: Box::new(condition),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+            span,
+        })
     }
 
     fn parse_logic_or(&mut self) -> Result<Expr> {
@@ -326,7 +754,7 @@ impl Parser {
     }
 
     fn parse_factor(&mut self) -> Result<Expr> {
-        let mut expr = self.parse_unary()?;
+        let mut expr = self.parse_power()?;
         loop {
             let op = if self.match_simple(&TokenKind::Star) {
                 Some(BinaryOp::Mul)
@@ -341,12 +769,21 @@ impl Parser {
             let Some(op) = op else {
                 break;
             };
-            let right = self.parse_unary()?;
+            let right = self.parse_power()?;
             expr = binary(op, expr, right);
         }
         Ok(expr)
     }
 
+    fn parse_power(&mut self) -> Result<Expr> {
+        let base = self.parse_unary()?;
+        if !self.match_simple(&TokenKind::StarStar) {
+            return Ok(base);
+        }
+        let exponent = self.parse_power()?;
+        Ok(binary(BinaryOp::Pow, base, exponent))
+    }
+
     fn parse_unary(&mut self) -> Result<Expr> {
         let token = self.peek().clone();
         if self.match_simple(&TokenKind::Bang) {
@@ -403,6 +840,9 @@ impl Parser {
                 value,
                 span: token.span,
             }),
+            TokenKind::Identifier(name) if self.at_simple(&TokenKind::LeftParen) => {
+                self.parse_builtin_call(name, token.span)
+            }
             TokenKind::Identifier(name) => Ok(Expr::Signal {
                 name,
                 span: token.span,
@@ -410,8 +850,14 @@ impl Parser {
             TokenKind::If => self.parse_conditional_expr(token.span.start),
             TokenKind::Case => self.parse_case_expr(token.span.start),
             TokenKind::LeftParen => {
+                self.open_parens.push(token.span);
                 let expr = self.parse_expr()?;
+                if !self.at_simple(&TokenKind::RightParen) && self.transposed_comparison().is_none()
+                {
+                    return Err(self.unclosed_paren());
+                }
                 self.expect_simple(TokenKind::RightParen, "`)`")?;
+                self.open_parens.pop();
                 Ok(expr)
             }
             other => Err(Diagnostic::at(
@@ -421,21 +867,89 @@ impl Parser {
         }
     }
 
-    fn parse_conditional_expr(&mut self, start: usize) -> Result<Expr> {
-        let condition = self.parse_expr()?;
-        self.expect_simple(TokenKind::LeftBrace, "`{`")?;
-        let then_expr = self.parse_expr()?;
-        self.expect_simple(TokenKind::RightBrace, "`}`")?;
-        self.expect_simple(TokenKind::Else, "`else`")?;
-        self.expect_simple(TokenKind::LeftBrace, "`{`")?;
-        let else_expr = self.parse_expr()?;
-        let end = self.expect_simple(TokenKind::RightBrace, "`}`")?.span.end;
+    /// Parse an `if` expression after its `if`, looping over an `else if`
+    /// chain and nesting the arms from the last one out.
+    fn parse_conditional_expr(&mut self, mut start: usize) -> Result<Expr> {
+        let mut arms = Vec::new();
+        let (mut expr, end) = loop {
+            let condition = self.parse_expr()?;
+            self.expect_simple(TokenKind::LeftBrace, "`{`")?;
+            let then_expr = self.parse_expr()?;
+            let then_end = self.expect_simple(TokenKind::RightBrace, "`}`")?.span.end;
+            if !self.at_simple(&TokenKind::Else) {
+                return Err(Diagnostic::at(
+                    Span::new(start, then_end),
+                    "`if` expression requires an `else` branch; only `if` statements in a process may omit it",
+                ));
+            }
+            self.expect_simple(TokenKind::Else, "`else`")?;
+            arms.push((start, condition, then_expr));
+            if !self.at_simple(&TokenKind::If) {
+                self.expect_simple(TokenKind::LeftBrace, "`{`")?;
+                let else_expr = self.parse_expr()?;
+                let end = self.expect_simple(TokenKind::RightBrace, "`}`")?.span.end;
+                break (else_expr, end);
+            }
+            start = self.bump().span.start;
+        };
 
-        Ok(Expr::Conditional {
-            condition: Box::new(condition),
-            then_expr: Box::new(then_expr),
-            else_expr: Box::new(else_expr),
-            span: Span::new(start, end),
+        for (start, condition, then_expr) in arms.into_iter().rev() {
+            expr = Expr::Conditional {
+                condition: Box::new(condition),
+                then_expr: Box::new(then_expr),
+                else_expr: Box::new(expr),
+                span: Span::new(start, end),
+            };
+        }
+        Ok(expr)
+    }
+
+    /// Parse a call to a built-in function into its binary operator; Frag
+    /// has no user-defined functions.
+    fn parse_builtin_call(&mut self, name: String, name_span: Span) -> Result<Expr> {
+        let Some(builtin) = Builtin::from_name(&name) else {
+            return Err(Diagnostic::at(
+                name_span,
+                format!(
+                    "Unknown function `{}`; the built-in functions are {}",
+                    name,
+                    Builtin::listed()
+                ),
+            ));
+        };
+        let open = self.bump().span;
+        self.open_parens.push(open);
+        let mut args = Vec::new();
+        if !self.at_simple(&TokenKind::RightParen) {
+            loop {
+                args.push(self.parse_expr()?);
+                if !self.match_simple(&TokenKind::Comma) || self.at_simple(&TokenKind::RightParen) {
+                    break;
+                }
+            }
+        }
+        if !self.at_simple(&TokenKind::RightParen) {
+            return Err(self.unclosed_paren());
+        }
+        let end = self.bump().span.end;
+        self.open_parens.pop();
+        let span = Span::new(name_span.start, end);
+
+        let [left, right] = <[Expr; 2]>::try_from(args).map_err(|args| {
+            Diagnostic::at(
+                span,
+                format!(
+                    "`{}` takes 2 arguments, found {}",
+                    builtin.name(),
+                    args.len()
+                ),
+            )
+        })?;
+        Ok(Expr::Binary {
+            op: builtin.op(),
+            left: Box::new(left),
+            right: Box::new(right),
+            span,
         })
     }
 
@@ -496,7 +1010,27 @@ impl Parser {
         })
     }
 
+    /// Report a missing `)` at the parenthesis that was left open.
+    ///
+    /// At end of input every open parenthesis is unmatched, so the first one
+    /// is reported; otherwise the innermost one ended at an unexpected token.
+    fn unclosed_paren(&self) -> Diagnostic {
+        let opener = if self.at_simple(&TokenKind::Eof) {
+            self.open_parens.first()
+        } else {
+            self.open_parens.last()
+        };
+        let span = opener.copied().unwrap_or(self.peek().span);
+        Diagnostic::at(
+            span,
+            format!("Unclosed `(`; expected `)`, found {}", self.peek().kind),
+        )
+    }
+
     fn expect_identifier(&mut self) -> Result<String> {
+        if self.peek().kind.is_keyword() {
+            return Err(self.reserved_keyword());
+        }
         let token = self.bump();
         match token.kind {
             TokenKind::Identifier(name) => Ok(name),
@@ -507,6 +1041,25 @@ impl Parser {
         }
     }
 
+    /// Detect a keyword used as an assignment target, such as `bit = 1;`.
+    fn at_keyword_assignment(&self) -> bool {
+        self.peek().kind.is_keyword()
+            && self.tokens.get(self.pos + 1).is_some_and(|next| {
+                next.kind == TokenKind::Equal || compound_assignment_op(&next.kind).is_some()
+            })
+    }
+
+    fn reserved_keyword(&self) -> Diagnostic {
+        let token = self.peek();
+        Diagnostic::at(
+            token.span,
+            format!(
+                "`{}` is a reserved keyword and cannot be used as a name",
+                token.kind
+            ),
+        )
+    }
+
     fn expect_index_literal(&mut self, context: &str) -> Result<(u32, Span)> {
         let token = self.bump();
         let TokenKind::Number(value) = token.kind else {
@@ -526,12 +1079,44 @@ impl Parser {
 
     fn expect_simple(&mut self, kind: TokenKind, expected: &str) -> Result<Token> {
         if self.at_simple(&kind) {
-            Ok(self.bump())
-        } else {
-            Err(Diagnostic::at(
-                self.peek().span,
-                format!("Expected {}, found {}", expected, self.peek().kind),
-            ))
+            return Ok(self.bump());
+        }
+        if let Some((span, written, intended)) = self.transposed_comparison() {
+            return Err(Diagnostic::at(
+                span,
+                format!(
+                    "Unknown operator `{}`; did you mean `{}`?",
+                    written, intended
+                ),
+            ));
+        }
+        Err(Diagnostic::at(
+            self.peek().span,
+            format!("Expected {}, found {}", expected, self.peek().kind),
+        ))
+    }
+
+    /// Detect `=<` or `=>` where a comparison operator was likely intended.
+    ///
+    /// Only consulted after an expectation fails, so a legitimate `=>` in a
+    /// case arm is never reported.
+    fn transposed_comparison(&self) -> Option<(Span, &'static str, &'static str)> {
+        let token = self.peek();
+        match token.kind {
+            TokenKind::FatArrow => Some((token.span, "=>", ">=")),
+            TokenKind::Equal => {
+                let next = self.tokens.get(self.pos + 1)?;
+                if next.span.start != token.span.end {
+                    return None;
+                }
+                let span = token.span.join(next.span);
+                match next.kind {
+                    TokenKind::Less => Some((span, "=<", "<=")),
+                    TokenKind::Greater => Some((span, "=>", ">=")),
+                    _ => None,
+                }
+            }
+            _ => None,
         }
     }
 
@@ -573,6 +1158,120 @@ fn unary(op: UnaryOp, start: usize, expr: Expr) -> Expr {
     }
 }
 
+fn file_label(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn respan_assignment(assignment: &mut Assignment, span: Span) {
+    assignment.span = span;
+    respan(&mut assignment.expr, span);
+}
+
+/// Replace every span in `expr` with `span`.
+fn respan(expr: &mut Expr, span: Span) {
+    match expr {
+        Expr::Number { span: own, .. }
+        | Expr::Bool { span: own, .. }
+        | Expr::Signal { span: own, .. } => *own = span,
+        Expr::Index {
+            expr, span: own, ..
+        }
+        | Expr::Slice {
+            expr, span: own, ..
+        }
+        | Expr::Unary {
+            expr, span: own, ..
+        } => {
+            *own = span;
+            respan(expr, span);
+        }
+        Expr::Binary {
+            left,
+            right,
+            span: own,
+            ..
+        } => {
+            *own = span;
+            respan(left, span);
+            respan(right, span);
+        }
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+            span: own,
+        } => {
+            *own = span;
+            respan(condition, span);
+            respan(then_expr, span);
+            respan(else_expr, span);
+        }
+        Expr::Case {
+            selector,
+            arms,
+            span: own,
+        } => {
+            *own = span;
+            respan(selector, span);
+            for arm in arms {
+                arm.span = span;
+                if let Some(pattern) = &mut arm.pattern {
+                    respan(pattern, span);
+                }
+                respan(&mut arm.value, span);
+            }
+        }
+    }
+}
+
+/// Build `target = if condition { .. } else { .. };` for one branch assignment.
+///
+/// The branch that did not assign the target reads it back, which holds the
+/// register's current value.
+fn select(
+    condition: &Expr,
+    span: Span,
+    assignment: Assignment,
+    other: Option<Expr>,
+    in_then_branch: bool,
+) -> Assignment {
+    let hold = other.unwrap_or_else(|| Expr::Signal {
+        name: assignment.target.clone(),
+        span: assignment.span,
+    });
+    let (then_expr, else_expr) = if in_then_branch {
+        (assignment.expr, hold)
+    } else {
+        (hold, assignment.expr)
+    };
+    Assignment {
+        target: assignment.target,
+        expr: Expr::Conditional {
+            condition: Box::new(condition.clone()),
+            then_expr: Box::new(then_expr),
+            else_expr: Box::new(else_expr),
+            span,
+        },
+        span: assignment.span,
+    }
+}
+
+/// The operator applied by a compound assignment such as `+=`, which
+/// `x += e;` desugars into `x = x + e;`.
+fn compound_assignment_op(kind: &TokenKind) -> Option<BinaryOp> {
+    match kind {
+        TokenKind::PlusEqual => Some(BinaryOp::Add),
+        TokenKind::MinusEqual => Some(BinaryOp::Sub),
+        TokenKind::StarEqual => Some(BinaryOp::Mul),
+        TokenKind::SlashEqual => Some(BinaryOp::Div),
+        TokenKind::PercentEqual => Some(BinaryOp::Mod),
+        _ => None,
+    }
+}
+
 fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
     let span = left.span().join(right.span());
     Expr::Binary {
@@ -5,7 +5,7 @@
 
 use crate::ast::{Assignment, BinaryOp, DeclKind, Expr, Module, UnaryOp};
 use crate::diagnostic::{Diagnostic, Result, Span};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 /// Semantic information produced for a checked module.
 #[derive(Clone, Debug)]
@@ -39,8 +39,21 @@ pub enum SymbolKind {
     Const,
 }
 
+/// Optional semantic checks.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AnalysisOptions {
+    /// Require `if` conditions and the operands of `!`, `&&`, and `||` to be
+    /// one bit wide instead of treating any nonzero value as true.
+    pub strict_conditions: bool,
+}
+
 /// Validate a parsed module and compute semantic metadata.
 pub fn analyze(module: &Module) -> Result<Analysis> {
+    analyze_with_options(module, &AnalysisOptions::default())
+}
+
+/// Validate a parsed module with optional checks enabled.
+pub fn analyze_with_options(module: &Module, options: &AnalysisOptions) -> Result<Analysis> {
     let mut symbols = BTreeMap::new();
 
     for decl in &module.declarations {
@@ -52,7 +65,8 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
                     "Duplicate declaration of `{}`; first declared near byte {}",
                     decl.name, previous.span.start
                 ),
-            ));
+            )
+            .with_code("E0002"));
         }
 
         let kind = SymbolKind::from(decl.kind);
@@ -84,7 +98,8 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
                             "Constant `{}` can only depend on constants, but `{}` is a {:?}",
                             decl.name, reference.name, symbol.kind
                         ),
-                    ));
+                    )
+                    .with_code("E0013"));
                 }
             }
             check_width(&decl.name, decl.ty.width, value, &symbols, decl.span)?;
@@ -101,7 +116,8 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
                     "Combinational assignment target `{}` must be an output or wire",
                     assignment.target
                 ),
-            ));
+            )
+            .with_code("E0005"));
         }
 
         if let Some(previous) = comb_targets.insert(assignment.target.clone(), assignment.span) {
@@ -111,7 +127,8 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
                     "Multiple combinational drivers for `{}`; previous assignment starts near byte {}",
                     assignment.target, previous.start
                 ),
-            ));
+            )
+            .with_code("E0004"));
         }
 
         check_expr(&assignment.expr, &symbols)?;
@@ -131,12 +148,14 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
                 process.span,
                 format!("Unknown clock signal `{}`", process.clock),
             )
+            .with_code("E0001")
         })?;
         if clock.kind != SymbolKind::Input || clock.width != 1 {
             return Err(Diagnostic::at(
                 process.span,
                 format!("Clock `{}` must be a one-bit input signal", process.clock),
-            ));
+            )
+            .with_code("E0009"));
         }
 
         let mut local_targets = HashMap::new();
@@ -149,7 +168,8 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
                         "Sequential assignment target `{}` must be a register",
                         assignment.target
                     ),
-                ));
+                )
+                .with_code("E0005"));
             }
             if let Some(previous) = local_targets.insert(assignment.target.clone(), assignment.span)
             {
@@ -159,7 +179,8 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
                         "Register `{}` is assigned twice in one process; previous assignment starts near byte {}",
                         assignment.target, previous.start
                     ),
-                ));
+                )
+                .with_code("E0006"));
             }
             if let Some(previous) = seq_targets.insert(assignment.target.clone(), assignment.span) {
                 return Err(Diagnostic::at(
@@ -168,7 +189,8 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
                         "Register `{}` is driven by multiple processes; previous assignment starts near byte {}",
                         assignment.target, previous.start
                     ),
-                ));
+                )
+                .with_code("E0006"));
             }
 
             check_expr(&assignment.expr, &symbols)?;
@@ -192,7 +214,14 @@ pub fn analyze(module: &Module) -> Result<Analysis> {
             return Err(Diagnostic::at(
                 decl.span,
                 format!("Output `{}` is declared but never assigned", decl.name),
-            ));
+            )
+            .with_code("E0007"));
+        }
+    }
+
+    if options.strict_conditions {
+        for expr in module_exprs(module) {
+            check_strict_conditions(expr, &symbols)?;
         }
     }
 
@@ -210,9 +239,9 @@ fn symbol_for_target<'a>(
     symbols: &'a BTreeMap<String, Symbol>,
     span: Span,
 ) -> Result<&'a Symbol> {
-    symbols
-        .get(name)
-        .ok_or_else(|| Diagnostic::at(span, format!("Unknown assignment target `{}`", name)))
+    symbols.get(name).ok_or_else(|| {
+        Diagnostic::at(span, format!("Unknown assignment target `{}`", name)).with_code("E0001")
+    })
 }
 
 fn check_expr(expr: &Expr, symbols: &BTreeMap<String, Symbol>) -> Result<()> {
@@ -222,7 +251,7 @@ fn check_expr(expr: &Expr, symbols: &BTreeMap<String, Symbol>) -> Result<()> {
             if symbols.contains_key(name) {
                 Ok(())
             } else {
-                Err(Diagnostic::at(*span, format!("Unknown signal `{}`", name)))
+                Err(Diagnostic::at(*span, format!("Unknown signal `{}`", name)).with_code("E0001"))
             }
         }
         Expr::Index { expr, index, span } => {
@@ -235,7 +264,8 @@ fn check_expr(expr: &Expr, symbols: &BTreeMap<String, Symbol>) -> Result<()> {
                         "Bit index {} is out of range for {} bit expression",
                         index, width
                     ),
-                ));
+                )
+                .with_code("E0010"));
             }
             Ok(())
         }
@@ -251,7 +281,8 @@ fn check_expr(expr: &Expr, symbols: &BTreeMap<String, Symbol>) -> Result<()> {
                 return Err(Diagnostic::at(
                     *span,
                     format!("Slice range must be descending; got [{}:{}]", msb, lsb),
-                ));
+                )
+                .with_code("E0010"));
             }
             if *msb >= width {
                 return Err(Diagnostic::at(
@@ -260,7 +291,8 @@ fn check_expr(expr: &Expr, symbols: &BTreeMap<String, Symbol>) -> Result<()> {
                         "Slice range [{}:{}] is out of range for {} bit expression",
                         msb, lsb, width
                     ),
-                ));
+                )
+                .with_code("E0010"));
             }
             Ok(())
         }
@@ -283,6 +315,86 @@ fn check_expr(expr: &Expr, symbols: &BTreeMap<String, Symbol>) -> Result<()> {
     }
 }
 
+fn module_exprs(module: &Module) -> impl Iterator<Item = &Expr> {
+    let constants = module
+        .declarations
+        .iter()
+        .filter_map(|decl| decl.value.as_ref());
+    let combinational = module.assignments.iter().map(|assignment| &assignment.expr);
+    let sequential = module
+        .processes
+        .iter()
+        .flat_map(|process| process.assignments.iter())
+        .map(|assignment| &assignment.expr);
+    constants.chain(combinational).chain(sequential)
+}
+
+fn check_strict_conditions(expr: &Expr, symbols: &BTreeMap<String, Symbol>) -> Result<()> {
+    match expr {
+        Expr::Number { .. } | Expr::Bool { .. } | Expr::Signal { .. } => Ok(()),
+        Expr::Index { expr, .. } | Expr::Slice { expr, .. } => {
+            check_strict_conditions(expr, symbols)
+        }
+        Expr::Unary { op, expr, .. } => {
+            if *op == UnaryOp::LogicNot {
+                require_one_bit_condition(expr, "`!` operand", symbols)?;
+            }
+            check_strict_conditions(expr, symbols)
+        }
+        Expr::Binary {
+            op, left, right, ..
+        } => {
+            if matches!(op, BinaryOp::LogicAnd | BinaryOp::LogicOr) {
+                let context = format!("`{}` operand", op);
+                require_one_bit_condition(left, &context, symbols)?;
+                require_one_bit_condition(right, &context, symbols)?;
+            }
+            check_strict_conditions(left, symbols)?;
+            check_strict_conditions(right, symbols)
+        }
+        Expr::Conditional {
+            condition,
+            then_expr,
+            else_expr,
+            ..
+        } => {
+            require_one_bit_condition(condition, "`if` condition", symbols)?;
+            check_strict_conditions(condition, symbols)?;
+            check_strict_conditions(then_expr, symbols)?;
+            check_strict_conditions(else_expr, symbols)
+        }
+        Expr::Case { selector, arms, .. } => {
+            check_strict_conditions(selector, symbols)?;
+            for arm in arms {
+                if let Some(pattern) = &arm.pattern {
+                    check_strict_conditions(pattern, symbols)?;
+                }
+                check_strict_conditions(&arm.value, symbols)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn require_one_bit_condition(
+    expr: &Expr,
+    context: &str,
+    symbols: &BTreeMap<String, Symbol>,
+) -> Result<()> {
+    let width = expr_width(expr, symbols);
+    if width == 1 {
+        return Ok(());
+    }
+    Err(Diagnostic::at(
+        expr.span(),
+        format!(
+            "Strict mode requires {} to be one bit, found {} bit(s); compare explicitly, for example `!= 0`",
+            context, width
+        ),
+    )
+    .with_code("E0012"))
+}
+
 fn check_case_expr(
     selector: &Expr,
     arms: &[crate::ast::CaseArm],
@@ -304,13 +416,15 @@ fn check_case_expr(
                             "Case pattern `{}` does not fit selector width of {} bit(s)",
                             value, selector_width
                         ),
-                    ));
+                    )
+                    .with_code("E0011"));
                 }
                 if !constant_patterns.insert(value) {
                     return Err(Diagnostic::at(
                         arm.span,
                         format!("Duplicate constant case pattern `{}`", value),
-                    ));
+                    )
+                    .with_code("E0011"));
                 }
             } else {
                 let pattern_width = expr_width(pattern, symbols);
@@ -321,7 +435,8 @@ fn check_case_expr(
                             "Case pattern is {} bit(s), wider than selector width of {} bit(s)",
                             pattern_width, selector_width
                         ),
-                    ));
+                    )
+                    .with_code("E0011"));
                 }
             }
         } else {
@@ -332,14 +447,15 @@ fn check_case_expr(
 
     match defaults {
         1 => Ok(()),
-        0 => Err(Diagnostic::at(
-            selector.span(),
-            "Case expression requires an `else` arm",
-        )),
+        0 => Err(
+            Diagnostic::at(selector.span(), "Case expression requires an `else` arm")
+                .with_code("E0011"),
+        ),
         _ => Err(Diagnostic::at(
             selector.span(),
             "Case expression can contain only one `else` arm",
-        )),
+        )
+        .with_code("E0011")),
     }
 }
 
@@ -367,7 +483,20 @@ fn check_width(
             "Width mismatch assigning to `{}`: target is {} bit(s), expression is {} bit(s)",
             target_name, target_width, expr_width
         ),
-    ))
+    )
+    .with_code("E0003"))
+}
+
+/// Return the names of all signals and constants referenced by `expr`.
+///
+/// Frag expressions introduce no local bindings, so every referenced name is
+/// free and must be supplied by a module declaration. This works on unchecked
+/// expressions and does not consult a symbol table.
+pub fn free_signals(expr: &Expr) -> BTreeSet<String> {
+    signal_refs(expr)
+        .into_iter()
+        .map(|reference| reference.name)
+        .collect()
 }
 
 /// Compute expression width using the checked symbol table.
@@ -399,7 +528,7 @@ pub fn expr_width(expr: &Expr, symbols: &BTreeMap<String, Symbol>) -> u32 {
             | BinaryOp::Ne
             | BinaryOp::LogicAnd
             | BinaryOp::LogicOr => 1,
-            BinaryOp::Shl | BinaryOp::Shr => expr_width(left, symbols),
+            BinaryOp::Shl | BinaryOp::Shr | BinaryOp::Pow => expr_width(left, symbols),
             _ => expr_width(left, symbols).max(expr_width(right, symbols)),
         },
         Expr::Conditional {
@@ -456,6 +585,9 @@ fn eval_unsized_const(expr: &Expr) -> Option<u128> {
                 BinaryOp::Mul => left.checked_mul(right),
                 BinaryOp::Div => left.checked_div(right),
                 BinaryOp::Mod => left.checked_rem(right),
+                BinaryOp::Pow => u32::try_from(right)
+                    .ok()
+                    .and_then(|exponent| left.checked_pow(exponent)),
                 BinaryOp::Shl => checked_shift(right).and_then(|shift| left.checked_shl(shift)),
                 BinaryOp::Shr => checked_shift(right).and_then(|shift| left.checked_shr(shift)),
                 BinaryOp::Lt => Some((left < right) as u128),
@@ -469,6 +601,8 @@ fn eval_unsized_const(expr: &Expr) -> Option<u128> {
                 BinaryOp::BitOr => Some(left | right),
                 BinaryOp::LogicAnd => Some(((left != 0) && (right != 0)) as u128),
                 BinaryOp::LogicOr => Some(((left != 0) || (right != 0)) as u128),
+                BinaryOp::Min => Some(left.min(right)),
+                BinaryOp::Max => Some(left.max(right)),
             }
         }
         Expr::Conditional {
@@ -576,47 +710,80 @@ fn combinational_order(assignments: &[Assignment]) -> Result<Vec<usize>> {
     topo_order(&deps, &labels, &spans, "combinational reference")
 }
 
-fn visit(
-    idx: usize,
+fn topo_order(
     deps: &[Vec<usize>],
     labels: &[String],
     spans: &[Span],
     cycle_name: &str,
-    marks: &mut [VisitMark],
-    order: &mut Vec<usize>,
-) -> Result<()> {
-    match marks[idx] {
-        VisitMark::Done => return Ok(()),
-        VisitMark::Visiting => {
-            return Err(Diagnostic::at(
-                spans[idx],
-                format!("Circular {} involving `{}`", cycle_name, labels[idx]),
-            ));
-        }
-        VisitMark::Fresh => {}
+) -> Result<Vec<usize>> {
+    let mut sort = TopoSort {
+        deps,
+        labels,
+        spans,
+        cycle_name,
+        marks: vec![VisitMark::Fresh; deps.len()],
+        path: Vec::new(),
+        order: Vec::new(),
+    };
+    for idx in 0..deps.len() {
+        sort.visit(idx)?;
     }
+    Ok(sort.order)
+}
 
-    marks[idx] = VisitMark::Visiting;
-    for dep in &deps[idx] {
-        visit(*dep, deps, labels, spans, cycle_name, marks, order)?;
-    }
-    marks[idx] = VisitMark::Done;
-    order.push(idx);
-    Ok(())
+/// Depth-first traversal state for [`topo_order`].
+struct TopoSort<'a> {
+    deps: &'a [Vec<usize>],
+    labels: &'a [String],
+    spans: &'a [Span],
+    cycle_name: &'a str,
+    marks: Vec<VisitMark>,
+    /// Nodes currently being visited, so reaching one of them again closes a
+    /// cycle that can be reported in full.
+    path: Vec<usize>,
+    order: Vec<usize>,
 }
 
-fn topo_order(
-    deps: &[Vec<usize>],
-    labels: &[String],
-    spans: &[Span],
-    cycle_name: &str,
-) -> Result<Vec<usize>> {
-    let mut marks = vec![VisitMark::Fresh; deps.len()];
-    let mut order = Vec::new();
-    for idx in 0..deps.len() {
-        visit(idx, deps, labels, spans, cycle_name, &mut marks, &mut order)?;
+impl TopoSort<'_> {
+    fn visit(&mut self, idx: usize) -> Result<()> {
+        match self.marks[idx] {
+            VisitMark::Done => return Ok(()),
+            VisitMark::Visiting => return Err(self.cycle_error(idx)),
+            VisitMark::Fresh => {}
+        }
+
+        self.marks[idx] = VisitMark::Visiting;
+        self.path.push(idx);
+        for dep in &self.deps[idx] {
+            self.visit(*dep)?;
+        }
+        self.path.pop();
+        self.marks[idx] = VisitMark::Done;
+        self.order.push(idx);
+        Ok(())
+    }
+
+    fn cycle_error(&self, idx: usize) -> Diagnostic {
+        let start = self
+            .path
+            .iter()
+            .position(|visiting| *visiting == idx)
+            .expect("visiting nodes are on the path");
+        let cycle = self.path[start..]
+            .iter()
+            .chain([&idx])
+            .map(|node| self.labels[*node].as_str())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        Diagnostic::at(
+            self.spans[idx],
+            format!(
+                "Circular {} involving `{}`: {}",
+                self.cycle_name, self.labels[idx], cycle
+            ),
+        )
+        .with_code("E0008")
     }
-    Ok(order)
 }
 
 #[derive(Clone, Copy, Eq, PartialEq)]
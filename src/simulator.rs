@@ -10,12 +10,29 @@ use std::collections::{BTreeMap, BTreeSet};
 use std::fmt;
 
 /// Simulation options.
+///
+/// Start from [`SimOptions::default`] and adjust it with the builder methods,
+/// so new options can be added without breaking callers.
 #[derive(Clone, Debug)]
+#[non_exhaustive]
 pub struct SimOptions {
     /// Number of ticks for sequential simulation.
     pub ticks: usize,
     /// Input overrides keyed by signal name.
     pub inputs: BTreeMap<String, u128>,
+    /// Seed for pseudo-random stimulus on inputs without overrides.
+    ///
+    /// When set, combinational modules produce `ticks` random rows and
+    /// sequential modules drive fresh random input values on every tick.
+    /// The same seed always produces the same stimulus.
+    pub seed: Option<u64>,
+    /// Fail when `+`, `-`, `*`, or `**` produces a value that does not fit
+    /// in its result width, instead of wrapping.
+    ///
+    /// Only operations on the evaluated path are checked: the branch a mux
+    /// or case does not select, and the right operand of a short-circuited
+    /// `&&` or `||`, cannot overflow.
+    pub checked: bool,
 }
 
 impl Default for SimOptions {
@@ -23,10 +40,44 @@ impl Default for SimOptions {
         Self {
             ticks: 8,
             inputs: BTreeMap::new(),
+            seed: None,
+            checked: false,
         }
     }
 }
 
+impl SimOptions {
+    /// Set the number of ticks for sequential simulation.
+    pub fn ticks(mut self, ticks: usize) -> Self {
+        self.ticks = ticks;
+        self
+    }
+
+    /// Override one input with a fixed value.
+    pub fn input(mut self, name: impl Into<String>, value: u128) -> Self {
+        self.inputs.insert(name.into(), value);
+        self
+    }
+
+    /// Override several inputs with fixed values.
+    pub fn inputs(mut self, inputs: impl IntoIterator<Item = (String, u128)>) -> Self {
+        self.inputs.extend(inputs);
+        self
+    }
+
+    /// Drive inputs without overrides from a pseudo-random seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Report arithmetic overflow instead of wrapping.
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+}
+
 /// Result of running the simulator.
 #[derive(Clone, Debug)]
 pub enum SimulationResult {
@@ -135,19 +186,39 @@ fn run_truth_table(module: &IrModule, options: &SimOptions) -> Result<TruthTable
         })?;
     }
 
-    let combinations = if options.inputs.is_empty() && total_input_width <= 8 {
+    let combinations = if options.seed.is_some() {
+        options.ticks.max(1) as u128
+    } else if options.inputs.is_empty() && total_input_width <= 8 {
         1u128 << total_input_width
     } else {
         1
     };
 
+    let mut rng = options.seed.map(Rng::new);
     let mut rows = Vec::new();
     for combo in 0..combinations {
         let mut values = initial_values(module, &options.inputs)?;
-        if options.inputs.is_empty() {
+        if let Some(rng) = rng.as_mut() {
+            apply_random_inputs(module, &options.inputs, &BTreeSet::new(), rng, &mut values);
+        } else if options.inputs.is_empty() {
             apply_combo_inputs(module, combo, &mut values);
         }
-        evaluate_combinational(module, &mut values);
+        evaluate_combinational(module, &mut values, options.checked).map_err(|error| {
+            let inputs = module
+                .signals
+                .iter()
+                .filter(|signal| signal.kind == IrSignalKind::Input)
+                .map(|signal| format!("{}={}", signal.name, values[&signal.name]))
+                .collect::<Vec<_>>();
+            if inputs.is_empty() {
+                return error;
+            }
+            Diagnostic::new(format!(
+                "{} with inputs {}",
+                error.message,
+                inputs.join(", ")
+            ))
+        })?;
         rows.push(values);
     }
 
@@ -185,6 +256,7 @@ fn run_waveform(module: &IrModule, options: &SimOptions) -> Result<Waveform> {
         .iter()
         .map(|name| (name.clone(), Vec::with_capacity(ticks)))
         .collect::<BTreeMap<_, _>>();
+    let mut rng = options.seed.map(Rng::new);
 
     for tick in 0..ticks {
         let mut previous_clocks = BTreeMap::new();
@@ -199,8 +271,13 @@ fn run_waveform(module: &IrModule, options: &SimOptions) -> Result<Waveform> {
                 values.insert(clock.clone(), (tick % 2) as u128);
             }
         }
+        if let Some(rng) = rng.as_mut() {
+            apply_random_inputs(module, &options.inputs, &clocks, rng, &mut values);
+        }
 
-        evaluate_combinational(module, &mut values);
+        let at_tick =
+            |error: Diagnostic| Diagnostic::new(format!("{} at tick {}", error.message, tick));
+        evaluate_combinational(module, &mut values, options.checked).map_err(at_tick)?;
 
         let mut pending = BTreeMap::new();
         for process in &module.processes {
@@ -219,6 +296,10 @@ fn run_waveform(module: &IrModule, options: &SimOptions) -> Result<Waveform> {
                     .signal(&assignment.target)
                     .map(|signal| signal.width)
                     .unwrap_or(128);
+                if options.checked {
+                    check_overflow(&assignment.target, &assignment.expr, &values)
+                        .map_err(at_tick)?;
+                }
                 let value = eval_expr(&assignment.expr, &values);
                 pending.insert(assignment.target.clone(), mask(value, width));
             }
@@ -228,7 +309,7 @@ fn run_waveform(module: &IrModule, options: &SimOptions) -> Result<Waveform> {
             values.insert(name, value);
         }
 
-        evaluate_combinational(module, &mut values);
+        evaluate_combinational(module, &mut values, options.checked).map_err(at_tick)?;
         for name in &names {
             let value = values.get(name).copied().unwrap_or(0);
             traces
@@ -308,7 +389,27 @@ fn apply_combo_inputs(module: &IrModule, combo: u128, values: &mut BTreeMap<Stri
     }
 }
 
-fn evaluate_combinational(module: &IrModule, values: &mut BTreeMap<String, u128>) {
+fn apply_random_inputs(
+    module: &IrModule,
+    overrides: &BTreeMap<String, u128>,
+    clocks: &BTreeSet<String>,
+    rng: &mut Rng,
+    values: &mut BTreeMap<String, u128>,
+) {
+    for signal in module.signals.iter().filter(|signal| {
+        signal.kind == IrSignalKind::Input
+            && !overrides.contains_key(&signal.name)
+            && !clocks.contains(&signal.name)
+    }) {
+        values.insert(signal.name.clone(), rng.next_value(signal.width));
+    }
+}
+
+fn evaluate_combinational(
+    module: &IrModule,
+    values: &mut BTreeMap<String, u128>,
+    checked: bool,
+) -> Result<()> {
     for constant in &module.constants {
         let value = eval_expr(&constant.expr, values);
         values.insert(constant.name.clone(), mask(value, constant.width));
@@ -319,12 +420,109 @@ fn evaluate_combinational(module: &IrModule, values: &mut BTreeMap<String, u128>
             .signal(&assignment.target)
             .map(|signal| signal.width)
             .unwrap_or(128);
+        if checked {
+            check_overflow(&assignment.target, &assignment.expr, values)?;
+        }
         let value = eval_expr(&assignment.expr, values);
         values.insert(assignment.target.clone(), mask(value, width));
     }
+    Ok(())
 }
 
-fn eval_expr(expr: &IrExpr, values: &BTreeMap<String, u128>) -> u128 {
+fn check_overflow(target: &str, expr: &IrExpr, values: &BTreeMap<String, u128>) -> Result<()> {
+    match find_overflow(expr, values) {
+        Some(detail) => Err(Diagnostic::new(format!(
+            "Arithmetic overflow assigning `{}`: {}",
+            target, detail
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Describe the first `+`, `-`, `*`, or `**` on the evaluated path whose
+/// exact result does not fit in its width.
+pub(crate) fn find_overflow(expr: &IrExpr, values: &BTreeMap<String, u128>) -> Option<String> {
+    match expr {
+        IrExpr::Const { .. } | IrExpr::Signal { .. } => None,
+        IrExpr::Slice { expr, .. } | IrExpr::Unary { expr, .. } => find_overflow(expr, values),
+        IrExpr::Binary {
+            op: op @ (BinaryOp::LogicAnd | BinaryOp::LogicOr),
+            left,
+            right,
+            ..
+        } => find_overflow(left, values).or_else(|| {
+            let decided = (eval_expr(left, values) != 0) == (*op == BinaryOp::LogicOr);
+            if decided {
+                None
+            } else {
+                find_overflow(right, values)
+            }
+        }),
+        IrExpr::Binary {
+            op,
+            left,
+            right,
+            width,
+        } => {
+            if let Some(detail) =
+                find_overflow(left, values).or_else(|| find_overflow(right, values))
+            {
+                return Some(detail);
+            }
+            let (lhs, rhs) = (eval_expr(left, values), eval_expr(right, values));
+            let exact = match op {
+                BinaryOp::Add => lhs.checked_add(rhs),
+                BinaryOp::Sub => lhs.checked_sub(rhs),
+                BinaryOp::Mul => lhs.checked_mul(rhs),
+                BinaryOp::Pow => u32::try_from(rhs)
+                    .ok()
+                    .and_then(|exponent| lhs.checked_pow(exponent)),
+                _ => return None,
+            };
+            match exact {
+                Some(value) if value <= mask(u128::MAX, *width) => None,
+                _ => Some(format!(
+                    "{} {} {} does not fit in {} bit(s)",
+                    lhs, op, rhs, width
+                )),
+            }
+        }
+        IrExpr::Mux {
+            select,
+            when_true,
+            when_false,
+            ..
+        } => find_overflow(select, values).or_else(|| {
+            if eval_expr(select, values) != 0 {
+                find_overflow(when_true, values)
+            } else {
+                find_overflow(when_false, values)
+            }
+        }),
+        IrExpr::Case { selector, arms, .. } => {
+            if let Some(detail) = find_overflow(selector, values) {
+                return Some(detail);
+            }
+            let selector = eval_expr(selector, values);
+            let mut default = None;
+            for arm in arms {
+                let Some(pattern) = &arm.pattern else {
+                    default = Some(&arm.value);
+                    continue;
+                };
+                if let Some(detail) = find_overflow(pattern, values) {
+                    return Some(detail);
+                }
+                if eval_expr(pattern, values) == selector {
+                    return find_overflow(&arm.value, values);
+                }
+            }
+            default.and_then(|value| find_overflow(value, values))
+        }
+    }
+}
+
+pub(crate) fn eval_expr(expr: &IrExpr, values: &BTreeMap<String, u128>) -> u128 {
     let value = match expr {
         IrExpr::Const { value, .. } => *value,
         IrExpr::Signal { name, .. } => values.get(name).copied().unwrap_or(0),
@@ -348,6 +546,7 @@ fn eval_expr(expr: &IrExpr, values: &BTreeMap<String, u128>) -> u128 {
                 BinaryOp::Mul => left.wrapping_mul(right),
                 BinaryOp::Div => left.checked_div(right).unwrap_or(0),
                 BinaryOp::Mod => left.checked_rem(right).unwrap_or(0),
+                BinaryOp::Pow => wrapping_pow(left, right),
                 BinaryOp::Shl => checked_shift(right)
                     .and_then(|shift| left.checked_shl(shift))
                     .unwrap_or(0),
@@ -365,6 +564,8 @@ fn eval_expr(expr: &IrExpr, values: &BTreeMap<String, u128>) -> u128 {
                 BinaryOp::BitOr => left | right,
                 BinaryOp::LogicAnd => ((left != 0) && (right != 0)) as u128,
                 BinaryOp::LogicOr => ((left != 0) || (right != 0)) as u128,
+                BinaryOp::Min => left.min(right),
+                BinaryOp::Max => left.max(right),
             }
         }
         IrExpr::Mux {
@@ -400,10 +601,58 @@ fn eval_expr(expr: &IrExpr, values: &BTreeMap<String, u128>) -> u128 {
     mask(value, expr.width())
 }
 
+/// Raise `base` to `exponent` modulo 2^128 by square-and-multiply.
+fn wrapping_pow(mut base: u128, mut exponent: u128) -> u128 {
+    let mut result = 1u128;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exponent >>= 1;
+    }
+    result
+}
+
 fn checked_shift(value: u128) -> Option<u32> {
     u32::try_from(value).ok()
 }
 
+/// Small deterministic xorshift generator for seeded stimulus.
+///
+/// The seed is scrambled with splitmix64 first so that nearby seeds, including
+/// zero, still produce well-mixed and distinct sequences.
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Self {
+            state: if z == 0 { 1 } else { z },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    fn next_value(&mut self, width: u32) -> u128 {
+        let high = self.next_u64() as u128;
+        let low = self.next_u64() as u128;
+        mask((high << 64) | low, width)
+    }
+}
+
 fn signal_names(module: &IrModule, kind: IrSignalKind) -> Vec<String> {
     module
         .signals
@@ -440,7 +689,7 @@ fn push_unique(values: &mut Vec<String>, value: String) {
     }
 }
 
-fn mask(value: u128, width: u32) -> u128 {
+pub(crate) fn mask(value: u128, width: u32) -> u128 {
     if width >= 128 {
         value
     } else {
@@ -0,0 +1,87 @@
+//! Assertion helpers for tests that compile and simulate Frag source.
+//!
+//! This module is available with the `testing` feature. It is used by the
+//! crate's own integration tests and is intended for downstream tools that
+//! want the same compact source-to-simulation assertions.
+
+use crate::compile;
+use crate::simulator::{self, SimOptions, SimulationResult};
+use std::collections::BTreeMap;
+
+/// Compile `source`, simulate one combinational evaluation with `inputs`, and
+/// assert that every named signal in `expected` has the expected value.
+///
+/// # Panics
+///
+/// Panics with the source text, the input values, and a per-signal
+/// expected/actual listing when compilation fails, the module is sequential,
+/// or any expected value differs.
+pub fn assert_simulates_to(source: &str, inputs: &[(&str, u128)], expected: &[(&str, u128)]) {
+    let row = simulate_row(source, inputs);
+
+    let mismatches = expected
+        .iter()
+        .filter(|(name, value)| row.get(*name) != Some(value))
+        .collect::<Vec<_>>();
+    if mismatches.is_empty() {
+        return;
+    }
+
+    let details = mismatches
+        .iter()
+        .map(|(name, value)| match row.get(*name) {
+            Some(actual) => format!("  `{}`: expected {}, found {}", name, value, actual),
+            None => format!(
+                "  `{}`: expected {}, but no such signal exists",
+                name, value
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    panic!(
+        "simulation result mismatch\ninputs: {}\n{}\nsource:\n{}",
+        format_inputs(inputs),
+        details,
+        source.trim()
+    );
+}
+
+fn simulate_row(source: &str, inputs: &[(&str, u128)]) -> BTreeMap<String, u128> {
+    let compiled = compile(source).unwrap_or_else(|error| {
+        panic!(
+            "source failed to compile:\n{}\nsource:\n{}",
+            error.with_source(source),
+            source.trim()
+        )
+    });
+    let options = SimOptions::default().ticks(1).inputs(
+        inputs
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value)),
+    );
+    let result = simulator::run(&compiled.ir, &options).unwrap_or_else(|error| {
+        panic!(
+            "simulation failed: {}\nsource:\n{}",
+            error.message,
+            source.trim()
+        )
+    });
+    let SimulationResult::TruthTable(mut table) = result else {
+        panic!(
+            "`assert_simulates_to` requires a combinational module\nsource:\n{}",
+            source.trim()
+        );
+    };
+    table.rows.swap_remove(0)
+}
+
+fn format_inputs(inputs: &[(&str, u128)]) -> String {
+    if inputs.is_empty() {
+        return "(none)".to_string();
+    }
+    inputs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -4,6 +4,7 @@
 
 use crate::ast::{BinaryOp, Edge, UnaryOp};
 use crate::ir::{IrExpr, IrModule, IrSignalKind};
+use std::collections::BTreeSet;
 
 /// Emit Verilog for an IR module.
 pub fn emit(module: &IrModule) -> String {
@@ -31,6 +32,11 @@ pub fn emit(module: &IrModule) -> String {
         out.push_str(");\n");
     }
 
+    for (op, width) in functions(module) {
+        out.push('\n');
+        out.push_str(&function_definition(op, width));
+    }
+
     if !module.constants.is_empty() || has_internal_signals(module) {
         out.push('\n');
     }
@@ -88,6 +94,90 @@ pub fn emit(module: &IrModule) -> String {
     out
 }
 
+/// `min` and `max` used in the module, keyed by operator and result width.
+///
+/// Each becomes a Verilog function so its arguments are written, and
+/// evaluated, once even when calls nest.
+fn functions(module: &IrModule) -> BTreeSet<(String, u32)> {
+    let mut functions = BTreeSet::new();
+    let exprs = module
+        .constants
+        .iter()
+        .map(|constant| &constant.expr)
+        .chain(
+            module
+                .combinational
+                .iter()
+                .map(|assignment| &assignment.expr),
+        )
+        .chain(
+            module
+                .processes
+                .iter()
+                .flat_map(|process| &process.assignments)
+                .map(|assignment| &assignment.expr),
+        );
+    for expr in exprs {
+        collect_functions(expr, &mut functions);
+    }
+    functions
+}
+
+fn collect_functions(expr: &IrExpr, functions: &mut BTreeSet<(String, u32)>) {
+    match expr {
+        IrExpr::Const { .. } | IrExpr::Signal { .. } => {}
+        IrExpr::Slice { expr, .. } | IrExpr::Unary { expr, .. } => {
+            collect_functions(expr, functions)
+        }
+        IrExpr::Binary {
+            op,
+            left,
+            right,
+            width,
+        } => {
+            if matches!(op, BinaryOp::Min | BinaryOp::Max) {
+                functions.insert((op.to_string(), *width));
+            }
+            collect_functions(left, functions);
+            collect_functions(right, functions);
+        }
+        IrExpr::Mux {
+            select,
+            when_true,
+            when_false,
+            ..
+        } => {
+            collect_functions(select, functions);
+            collect_functions(when_true, functions);
+            collect_functions(when_false, functions);
+        }
+        IrExpr::Case { selector, arms, .. } => {
+            collect_functions(selector, functions);
+            for arm in arms {
+                if let Some(pattern) = &arm.pattern {
+                    collect_functions(pattern, functions);
+                }
+                collect_functions(&arm.value, functions);
+            }
+        }
+    }
+}
+
+fn function_name(op: impl std::fmt::Display, width: u32) -> String {
+    format!("frag_{}_{}", op, width)
+}
+
+fn function_definition(op: String, width: u32) -> String {
+    let compare = if op == "min" { "<" } else { ">" };
+    let name = function_name(op, width);
+    format!(
+        "function{range} {name}(input{range} a, input{range} b);\n    {name} = (a {compare} b) ? a : b;\nendfunction\n",
+        range = range(width),
+        name = name,
+        compare = compare
+    )
+}
+
 fn has_internal_signals(module: &IrModule) -> bool {
     module
         .signals
@@ -130,6 +220,32 @@ pub fn expr(expr: &IrExpr) -> String {
             width,
         } => slice_expr(expr, *msb, *lsb, *width),
         IrExpr::Unary { op, expr, .. } => format!("({}{})", unary(*op), self::expr(expr)),
+        IrExpr::Binary {
+            op: op @ (BinaryOp::Div | BinaryOp::Mod),
+            left,
+            right,
+            ..
+        } if !matches!(**right, IrExpr::Const { value, .. } if value != 0) => {
+            let right = self::expr(right);
+            format!(
+                "({} == 0 ? 0 : ({} {} {}))",
+                right,
+                self::expr(left),
+                binary(*op),
+                right
+            )
+        }
+        IrExpr::Binary {
+            op: op @ (BinaryOp::Min | BinaryOp::Max),
+            left,
+            right,
+            width,
+        } => format!(
+            "{}({}, {})",
+            function_name(*op, *width),
+            self::expr(left),
+            self::expr(right)
+        ),
         IrExpr::Binary {
             op, left, right, ..
         } => format!(
@@ -210,6 +326,7 @@ fn binary(op: BinaryOp) -> &'static str {
         BinaryOp::Mul => "*",
         BinaryOp::Div => "/",
         BinaryOp::Mod => "%",
+        BinaryOp::Pow => "**",
         BinaryOp::Shl => "<<",
         BinaryOp::Shr => ">>",
         BinaryOp::Lt => "<",
@@ -223,5 +340,6 @@ fn binary(op: BinaryOp) -> &'static str {
         BinaryOp::BitOr => "|",
         BinaryOp::LogicAnd => "&&",
         BinaryOp::LogicOr => "||",
+        BinaryOp::Min | BinaryOp::Max => unreachable!("emitted as functions"),
     }
 }
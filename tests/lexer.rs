@@ -0,0 +1,352 @@
+use frag_compiler::ast::{Expr, UnaryOp};
+use frag_compiler::diagnostic::Span;
+use frag_compiler::lexer::{lex, lex_with_defines, TokenKind};
+use frag_compiler::simulator::{SimOptions, SimulationResult};
+use frag_compiler::{compile, simulator};
+use std::collections::BTreeSet;
+
+fn numbers(source: &str) -> Vec<u128> {
+    lex(source)
+        .expect("source should lex")
+        .into_iter()
+        .filter_map(|token| match token.kind {
+            TokenKind::Number(value) => Some(value),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn hex_literals_accept_either_case() {
+    assert_eq!(
+        numbers("0xff 0xFF 0XfF 0x0 0x1_0"),
+        vec![255, 255, 255, 0, 16]
+    );
+}
+
+#[test]
+fn empty_hex_prefix_is_a_lexer_error() {
+    for source in ["0x", "0x;", "0x_"] {
+        let error = lex(source).expect_err("empty hex literal should fail");
+        assert!(
+            error.message.contains("has no digits after its prefix"),
+            "unexpected message for {:?}: {}",
+            source,
+            error.message
+        );
+        let span = error.span.expect("lexer diagnostic should have a span");
+        assert_eq!(span.start, 0);
+    }
+}
+
+#[test]
+fn binary_and_octal_literals() {
+    assert_eq!(
+        numbers("0b0 0o0 0b1010 0B1_1 0o755 0O17"),
+        vec![0, 0, 10, 3, 0o755, 0o17]
+    );
+}
+
+#[test]
+fn literals_up_to_u128_max_are_accepted() {
+    let max = u128::MAX;
+    let source = format!("{} 0x{:x} 0o{:o} 0b{:b}", max, max, max, max);
+    assert_eq!(numbers(&source), vec![max; 4]);
+
+    let error = lex("340282366920938463463374607431768211456").expect_err("u128 overflow");
+    assert!(error.message.contains("does not fit in 128 bits"));
+}
+
+#[test]
+fn invalid_radix_digits_are_lexer_errors() {
+    for source in ["0b102", "0o78", "0o", "12ab"] {
+        let error = lex(source).expect_err("invalid digits should fail");
+        let span = error.span.expect("lexer diagnostic should have a span");
+        assert!(span.start < span.end && span.end <= source.len());
+    }
+}
+
+#[test]
+fn malformed_literals_report_specific_reasons() {
+    let cases = [
+        ("0xff_", "Number literal `0xff_` ends with an underscore"),
+        ("1_", "Number literal `1_` ends with an underscore"),
+        ("0b_", "Number literal `0b_` has no digits after its prefix"),
+        (
+            "0x1_0000_0000_0000_0000_0000_0000_0000_0000",
+            "does not fit in 128 bits",
+        ),
+        ("1.5", "Fractional literal `1.5` is not supported"),
+    ];
+    for (literal, reason) in cases {
+        let source = format!("out = {};", literal);
+        let error = lex(&source).expect_err("malformed literal should fail");
+        assert!(
+            error.message.contains(reason),
+            "expected {:?} for {:?}, found {:?}",
+            reason,
+            literal,
+            error.message
+        );
+        let span = error.span.expect("lexer diagnostic should have a span");
+        assert_eq!(&source[span.start..span.end], literal);
+    }
+}
+
+#[test]
+fn invalid_digits_are_reported_at_their_position() {
+    let cases = [
+        ("0b102", "Invalid digit `2` in binary literal `0b102`", 4),
+        (
+            "0b1010_1012",
+            "Invalid digit `2` in binary literal `0b1010_1012`",
+            10,
+        ),
+        ("0o78", "Invalid digit `8` in octal literal `0o78`", 3),
+        ("12ab", "Invalid digit `a` in decimal literal `12ab`", 2),
+        ("0x1g", "Invalid digit `g` in hexadecimal literal `0x1g`", 3),
+    ];
+    for (literal, message, offset) in cases {
+        let source = format!("out = {};", literal);
+        let error = lex(&source).expect_err("invalid digit should fail");
+        assert_eq!(error.message, message);
+        let start = "out = ".len() + offset;
+        assert_eq!(error.span, Some(Span::new(start, start + 1)));
+    }
+}
+
+#[test]
+fn grouped_binary_literals_ignore_underscores() {
+    assert_eq!(numbers("0b1010_1010 0b_1111_0000 0b1_0"), vec![170, 240, 2]);
+}
+
+#[test]
+fn negated_hex_literal_parses_as_unary_minus() {
+    let source = r#"
+module NegHex {
+    output out: u5;
+    out = -0x10;
+}
+"#;
+
+    let compiled = compile(source).expect("negated hex literal should compile");
+    match &compiled.ast.assignments[0].expr {
+        Expr::Unary {
+            op: UnaryOp::Neg,
+            expr,
+            ..
+        } => assert!(matches!(**expr, Expr::Number { value: 16, .. })),
+        other => panic!("expected unary minus, found {:?}", other),
+    }
+
+    let SimulationResult::TruthTable(table) =
+        simulator::run(&compiled.ir, &SimOptions::default()).expect("simulation should succeed")
+    else {
+        panic!("module should be combinational");
+    };
+    assert_eq!(table.rows[0]["out"], 32 - 16);
+}
+
+fn kinds(source: &str) -> Vec<TokenKind> {
+    lex(source)
+        .expect("source should lex")
+        .into_iter()
+        .map(|token| token.kind)
+        .collect()
+}
+
+#[test]
+fn block_comments_span_lines_and_keep_division() {
+    let ident = |name: &str| TokenKind::Identifier(name.to_string());
+    assert_eq!(
+        kinds("a /* one\n two * / */ / b /**/ / c"),
+        vec![
+            ident("a"),
+            TokenKind::Slash,
+            ident("b"),
+            TokenKind::Slash,
+            ident("c"),
+            TokenKind::Eof,
+        ]
+    );
+}
+
+#[test]
+fn unterminated_block_comment_reports_its_start() {
+    let source = "a = 1; /* never closed\n b = 2;";
+    let error = lex(source).expect_err("unterminated block comment should fail");
+    assert!(error.message.contains("Unterminated block comment"));
+    let span = error.span.expect("lexer diagnostic should have a span");
+    assert_eq!(span.start, source.find("/*").unwrap());
+    assert_eq!(span.end, source.len());
+
+    let error = lex("/*/").expect_err("`/*/` does not close the comment");
+    assert!(error.message.contains("Unterminated block comment"));
+}
+
+#[test]
+fn block_comments_nest() {
+    let ident = |name: &str| TokenKind::Identifier(name.to_string());
+    assert_eq!(
+        kinds("a /* one /* two /* three */ two */ one */ b"),
+        vec![ident("a"), ident("b"), TokenKind::Eof]
+    );
+    assert_eq!(
+        kinds("/* outer /* inner */ still outer */ c"),
+        vec![ident("c"), TokenKind::Eof]
+    );
+}
+
+#[test]
+fn unbalanced_nested_block_comment_fails_at_eof() {
+    let source = "a /* outer /* inner */ b";
+    let error = lex(source).expect_err("unbalanced nested comment should fail");
+    assert!(error.message.contains("Unterminated block comment"));
+    let span = error.span.expect("lexer diagnostic should have a span");
+    assert_eq!(span.start, 2);
+    assert_eq!(span.end, source.len());
+}
+
+fn idents_with(source: &str, defines: &[&str]) -> Vec<String> {
+    let defines = defines
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<BTreeSet<_>>();
+    lex_with_defines(source, &defines)
+        .expect("source should lex")
+        .into_iter()
+        .filter_map(|token| match token.kind {
+            TokenKind::Identifier(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn feature_blocks_are_kept_only_when_defined() {
+    let source = "a\n#if FAST\nfast\n#endif\nb\n";
+    assert_eq!(idents_with(source, &[]), ["a", "b"]);
+    assert_eq!(idents_with(source, &["FAST"]), ["a", "fast", "b"]);
+    assert_eq!(kinds(source).len(), 3);
+}
+
+#[test]
+fn feature_blocks_nest() {
+    let source = "\
+#if OUTER
+outer
+  #if INNER
+  inner
+  #endif
+after_inner
+#endif
+tail
+";
+    assert_eq!(idents_with(source, &[]), ["tail"]);
+    assert_eq!(idents_with(source, &["INNER"]), ["tail"]);
+    assert_eq!(
+        idents_with(source, &["OUTER"]),
+        ["outer", "after_inner", "tail"]
+    );
+    assert_eq!(
+        idents_with(source, &["OUTER", "INNER"]),
+        ["outer", "inner", "after_inner", "tail"]
+    );
+}
+
+#[test]
+fn hash_comments_are_not_directives() {
+    let source = "# if this were a directive it would need a name\na # endif\nb #if X\nc\n";
+    assert_eq!(idents_with(source, &[]), ["a", "b", "c"]);
+}
+
+#[test]
+fn malformed_feature_blocks_are_errors() {
+    let cases = [
+        ("a\n#if FAST\nb\n", "Unterminated `#if`", "#if FAST"),
+        (
+            "#if FAST\n#if SLOW\n#endif\n",
+            "Unterminated `#if`",
+            "#if FAST",
+        ),
+        ("#endif\n", "without a matching `#if`", "#endif"),
+        ("#if\n#endif\n", "requires a feature name", "#if"),
+        ("#if A B\n#endif\n", "Unexpected `B`", "#if A B"),
+    ];
+    for (source, reason, spanned) in cases {
+        for defines in [&[][..], &["FAST"][..]] {
+            let defines = defines
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<BTreeSet<_>>();
+            let error = lex_with_defines(source, &defines).expect_err("directive should fail");
+            assert!(
+                error.message.contains(reason),
+                "expected {:?} for {:?}, found {:?}",
+                reason,
+                source,
+                error.message
+            );
+            let span = error.span.expect("directive diagnostic should have a span");
+            assert_eq!(&source[span.start..span.end], spanned);
+        }
+    }
+}
+
+#[test]
+fn token_locations_track_lines_and_columns() {
+    let source = "module M {\n\tout = a == b;\n  /* é */ x\n}";
+    let locations = lex(source)
+        .expect("source should lex")
+        .into_iter()
+        .map(|token| token.span.location(source).to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        locations,
+        ["1:1", "1:8", "1:10", "2:2", "2:6", "2:8", "2:10", "2:13", "2:14", "3:11", "4:1", "4:2"]
+    );
+}
+
+#[test]
+fn diagnostics_render_character_columns() {
+    let source = "// é\nout = 1.5;";
+    let error = lex(source).expect_err("fractional literal should fail");
+    let rendered = error.with_source(source);
+    assert!(rendered.contains("line 2, column 7"), "{}", rendered);
+    assert!(rendered.ends_with("out = 1.5;\n      ^^^"), "{}", rendered);
+}
+
+#[test]
+fn diagnostics_expand_tabs_under_the_caret() {
+    let source = "\tout =\t1.5;";
+    let error = lex(source).expect_err("fractional literal should fail");
+    let rendered = error.with_source(source);
+    assert!(rendered.contains("line 1, column 8"), "{}", rendered);
+    assert!(
+        rendered.ends_with("\n    out =   1.5;\n            ^^^"),
+        "{}",
+        rendered
+    );
+}
+
+#[test]
+fn string_literals_hold_include_paths() {
+    assert_eq!(
+        kinds("include \"lib/my masks.frag\";"),
+        vec![
+            TokenKind::Include,
+            TokenKind::Str("lib/my masks.frag".to_string()),
+            TokenKind::Semicolon,
+            TokenKind::Eof,
+        ]
+    );
+
+    for source in ["include \"open", "include \"open\n\";"] {
+        let error = lex(source).expect_err("unterminated string should fail");
+        assert_eq!(
+            error.message,
+            "Unterminated string literal; strings must close on the same line"
+        );
+        assert_eq!(error.span, Some(Span::new(8, 13)));
+    }
+}
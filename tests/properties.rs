@@ -1,6 +1,9 @@
+use frag_compiler::ast::{self, BinaryOp, Expr, UnaryOp};
+use frag_compiler::parser::parse_source;
 use frag_compiler::simulator::{SimOptions, SimulationResult};
 use frag_compiler::{compile, simulator};
 use std::collections::BTreeMap;
+use std::fs;
 
 #[test]
 fn mux4_case_matches_reference_model_for_all_selects() {
@@ -60,12 +63,158 @@ fn byte_slices_match_reference_model_for_all_inputs() {
     }
 }
 
+#[test]
+fn printed_expressions_use_minimal_parentheses() {
+    let cases = [
+        ("(a + b) * c", "(a + b) * c"),
+        ("a + (b * c)", "a + b * c"),
+        ("(a - b) - c", "a - b - c"),
+        ("a - (b - c)", "a - (b - c)"),
+        ("2 ** 3 ** 2", "2 ** 3 ** 2"),
+        ("(2 ** 3) ** 2", "(2 ** 3) ** 2"),
+        ("(-a) ** b", "-a ** b"),
+        ("-(a ** b)", "-(a ** b)"),
+        ("~(a & b)[3:0]", "~(a & b)[3:0]"),
+        ("((a[7]))", "a[7]"),
+        ("a == b || (c && d)", "a == b || c && d"),
+        ("(a || b) && c", "(a || b) && c"),
+        ("1 << (2 + 1)", "1 << 2 + 1"),
+        ("(1 << 2) + 1", "(1 << 2) + 1"),
+        ("!(a < b)", "!(a < b)"),
+        (
+            "if (a > b) { case a { 0 => b, else => a } } else { b } + 1",
+            "if a > b { case a { 0 => b, else => a } } else { b } + 1",
+        ),
+    ];
+    for (source, printed) in cases {
+        let expr = parse_expr(source);
+        assert_eq!(expr.to_string(), printed, "printing {}", source);
+        assert_eq!(
+            without_spans(&parse_expr(printed)),
+            without_spans(&expr),
+            "{} does not parse back to the tree of {}",
+            printed,
+            source
+        );
+    }
+}
+
+#[test]
+fn random_expressions_round_trip_through_source() {
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    for _ in 0..500 {
+        let expr = random_expr(&mut state, 5);
+        let printed = expr.to_string();
+        assert_eq!(
+            without_spans(&parse_expr(&printed)),
+            without_spans(&expr),
+            "{} does not parse back to the generated tree",
+            printed
+        );
+    }
+}
+
+#[test]
+fn printed_examples_parse_back_to_the_same_module() {
+    for entry in fs::read_dir("examples").expect("examples directory exists") {
+        let path = entry.expect("example entry is readable").path();
+        let source = fs::read_to_string(&path).expect("example is readable");
+        let module = parse_source(&source).expect("example parses");
+        let printed = module.to_string();
+        let reparsed = parse_source(&printed)
+            .unwrap_or_else(|error| panic!("{}: {}\n{}", path.display(), error, printed));
+        assert_eq!(
+            without_spans(&reparsed),
+            without_spans(&module),
+            "{} does not round-trip",
+            path.display()
+        );
+        assert_eq!(reparsed.to_string(), printed);
+    }
+}
+
+fn parse_expr(source: &str) -> Expr {
+    let module = parse_source(&format!("module M {{ out = {}; }}", source))
+        .unwrap_or_else(|error| panic!("{} should parse: {}", source, error));
+    module.assignments[0].expr.clone()
+}
+
+/// Render a value's `Debug` form with every span removed, so trees parsed from
+/// different text compare equal when only positions differ.
+fn without_spans(value: &impl std::fmt::Debug) -> String {
+    let text = format!("{:?}", value);
+    let mut result = String::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find("span: Span {") {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find('}').expect("span ends") + start + 1;
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn random_expr(state: &mut u64, depth: u32) -> Expr {
+    let names = ["a", "b", "c"];
+    let binary_ops = [
+        BinaryOp::Add,
+        BinaryOp::Sub,
+        BinaryOp::Mul,
+        BinaryOp::Div,
+        BinaryOp::Mod,
+        BinaryOp::Pow,
+        BinaryOp::Shl,
+        BinaryOp::Shr,
+        BinaryOp::Lt,
+        BinaryOp::Ge,
+        BinaryOp::Eq,
+        BinaryOp::Ne,
+        BinaryOp::BitAnd,
+        BinaryOp::BitXor,
+        BinaryOp::BitOr,
+        BinaryOp::LogicAnd,
+        BinaryOp::LogicOr,
+    ];
+    let unary_ops = [UnaryOp::LogicNot, UnaryOp::BitNot, UnaryOp::Neg];
+    let choice = if depth == 0 {
+        next(state) % 2
+    } else {
+        next(state) % 7
+    };
+    match choice {
+        0 => ast::num(next(state) as u128 % 20),
+        1 => ast::signal(names[next(state) as usize % names.len()]),
+        2 => ast::unary(
+            unary_ops[next(state) as usize % unary_ops.len()],
+            random_expr(state, depth - 1),
+        ),
+        3 => ast::index(random_expr(state, depth - 1), next(state) as u32 % 4),
+        4 => ast::conditional(
+            random_expr(state, depth - 1),
+            random_expr(state, depth - 1),
+            random_expr(state, depth - 1),
+        ),
+        _ => ast::binary(
+            binary_ops[next(state) as usize % binary_ops.len()],
+            random_expr(state, depth - 1),
+            random_expr(state, depth - 1),
+        ),
+    }
+}
+
+fn next(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
 fn simulate_one(
     module: &frag_compiler::ir::IrModule,
     inputs: BTreeMap<String, u128>,
 ) -> BTreeMap<String, u128> {
-    let result =
-        simulator::run(module, &SimOptions { ticks: 1, inputs }).expect("simulation should work");
+    let result = simulator::run(module, &SimOptions::default().ticks(1).inputs(inputs))
+        .expect("simulation should work");
     let SimulationResult::TruthTable(table) = result else {
         panic!("combinational module should produce a truth table");
     };
@@ -0,0 +1,70 @@
+use frag_compiler::testing::assert_simulates_to;
+use std::panic;
+
+const HALF_ADDER: &str = include_str!("../examples/half_adder.frag");
+
+#[test]
+fn assert_simulates_to_accepts_matching_outputs() {
+    assert_simulates_to(
+        HALF_ADDER,
+        &[("a", 1), ("b", 1)],
+        &[("sum", 0), ("carry", 1)],
+    );
+    assert_simulates_to(
+        HALF_ADDER,
+        &[("a", 1), ("b", 0)],
+        &[("sum", 1), ("carry", 0)],
+    );
+}
+
+#[test]
+fn assert_simulates_to_reports_wrong_result() {
+    let message = panic_message(|| {
+        assert_simulates_to(HALF_ADDER, &[("a", 1), ("b", 1)], &[("sum", 1)]);
+    });
+
+    assert!(message.contains("simulation result mismatch"), "{message}");
+    assert!(message.contains("inputs: a=1, b=1"), "{message}");
+    assert!(message.contains("`sum`: expected 1, found 0"), "{message}");
+    assert!(message.contains("module HalfAdder"), "{message}");
+}
+
+#[test]
+fn assert_simulates_to_reports_unknown_signal() {
+    let message = panic_message(|| {
+        assert_simulates_to(HALF_ADDER, &[], &[("overflow", 0)]);
+    });
+
+    assert!(
+        message.contains("`overflow`: expected 0, but no such signal exists"),
+        "{message}"
+    );
+}
+
+#[test]
+fn assert_simulates_to_reports_compile_errors_with_source() {
+    let message = panic_message(|| {
+        assert_simulates_to(
+            "module Broken { output y: bit; y = missing; }",
+            &[],
+            &[("y", 0)],
+        );
+    });
+
+    assert!(message.contains("source failed to compile"), "{message}");
+    assert!(message.contains("Unknown signal `missing`"), "{message}");
+}
+
+/// Run `check` and return its panic message.
+///
+/// The panic hook is left alone: it is process-wide, and swapping it while
+/// other tests run in parallel can leave it silenced for the whole binary.
+/// The test harness captures the expected panic's output instead.
+fn panic_message(check: impl FnOnce() + panic::UnwindSafe) -> String {
+    let payload = panic::catch_unwind(check).expect_err("assertion helper should panic");
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|text| text.to_string()))
+        .unwrap_or_default()
+}
@@ -1,7 +1,8 @@
 use std::env;
 use std::ffi::OsString;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 #[test]
@@ -202,6 +203,246 @@ fn cli_rejects_unknown_run_option() {
     );
 }
 
+#[test]
+fn cli_reports_overflowing_literal_without_panicking() {
+    let temp = fresh_temp_dir("frag-cli-overflow");
+    let path = temp.join("overflow.frag");
+    fs::write(
+        &path,
+        "module Overflow {\n    output out: bit;\n    out = 340282366920938463463374607431768211456;\n}\n",
+    )
+    .expect("write overflow source");
+
+    let output = Command::new(frag_bin())
+        .arg("check")
+        .arg(&path)
+        .output()
+        .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+
+    assert_eq!(output.status.code(), Some(1), "expected a clean failure");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("does not fit in 128 bits") && stderr.contains("line 3, column 11"),
+        "unexpected stderr: {stderr}"
+    );
+    assert!(!stderr.contains("panicked"), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn cli_optimized_run_still_reports_overflow() {
+    let temp = fresh_temp_dir("frag-cli-optimized-overflow");
+    let path = temp.join("bump.frag");
+    fs::write(
+        &path,
+        "module Bump {\n    const k: u8 = 200;\n    output y: u8;\n    y = k + 100;\n}\n",
+    )
+    .expect("write bump source");
+
+    for flags in [&["--checked"][..], &["-O", "--checked"]] {
+        let output = Command::new(frag_bin())
+            .arg("run")
+            .arg(&path)
+            .args(flags)
+            .output()
+            .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+        assert!(!output.status.success(), "expected {flags:?} run to fail");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            stderr.contains("200 + 100 does not fit in 8 bit(s)"),
+            "unexpected stderr for {flags:?}: {stderr}"
+        );
+    }
+}
+
+#[test]
+fn cli_reports_every_syntax_error() {
+    let temp = fresh_temp_dir("frag-cli-syntax-errors");
+    let path = temp.join("broken.frag");
+    fs::write(
+        &path,
+        "module Broken {\n    output a: bit;\n    output b: bit;\n    a = ;\n    b = );\n}\n",
+    )
+    .expect("write broken source");
+
+    let output = Command::new(frag_bin())
+        .arg("check")
+        .arg(&path)
+        .output()
+        .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+    assert!(!output.status.success(), "expected check to fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Expected expression, found ;\n --> line 4, column 9")
+            && stderr.contains("Expected expression, found )\n --> line 5, column 9"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_times_each_phase() {
+    let output = Command::new(frag_bin())
+        .arg("run")
+        .arg("examples/counter.frag")
+        .arg("--time")
+        .arg("--ticks")
+        .arg("2")
+        .output()
+        .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+    assert!(output.status.success(), "expected timed run to succeed");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let phases = stderr
+        .lines()
+        .map(|line| {
+            assert!(line.ends_with(" us"), "unexpected line: {line}");
+            line.split_whitespace().next().unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(phases, ["lex", "parse", "analyze", "simulate", "total"]);
+
+    let temp = fresh_temp_dir("frag-cli-time-output");
+    let output = Command::new(frag_bin())
+        .current_dir(&temp)
+        .arg("verilog")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("examples/counter.frag"))
+        .arg("-o")
+        .arg("--time")
+        .output()
+        .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+    assert!(output.status.success(), "expected verilog to succeed");
+    assert!(output.stderr.is_empty(), "unexpected timing output");
+    assert!(
+        temp.join("--time").is_file(),
+        "expected `--time` as the output path"
+    );
+}
+
+#[test]
+fn cli_explains_error_codes() {
+    let temp = fresh_temp_dir("frag-cli-explain");
+    let path = temp.join("unknown.frag");
+    fs::write(
+        &path,
+        "module Unknown {\n    output out: bit;\n    out = missing;\n}\n",
+    )
+    .expect("write unknown-signal source");
+
+    let output = Command::new(frag_bin())
+        .arg("check")
+        .arg(&path)
+        .output()
+        .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("[E0001] Unknown signal `missing`"),
+        "unexpected stderr: {stderr}"
+    );
+
+    let output = Command::new(frag_bin())
+        .arg("--explain")
+        .arg("E0001")
+        .output()
+        .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+    assert!(output.status.success(), "expected --explain to succeed");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with("A name was used that no declaration introduces."),
+        "unexpected stdout: {stdout}"
+    );
+
+    let output = Command::new(frag_bin())
+        .arg("--explain")
+        .arg("E9999")
+        .output()
+        .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+    assert!(!output.status.success(), "expected unknown code to fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Unknown error code `E9999`"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_repl_evaluates_lines_and_keeps_constants() {
+    let mut child = Command::new(frag_bin())
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(b"const k: u8 = 200;\nk + 100\nmissing + 1\n(1 +\nk >> 2\nk + 1 // note\nk + 2; // done\n:quit\nk\n")
+        .expect("write REPL input");
+    let output = child.wait_with_output().expect("REPL exits");
+
+    assert!(output.status.success(), "expected REPL to exit cleanly");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let values = stdout
+        .split("frag> ")
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        values,
+        ["44 : u8", "50 : u8", "201 : u8", "202 : u8"],
+        "unexpected stdout: {stdout}"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("[E0001] Unknown signal `missing`") && stderr.contains("Expected"),
+        "unexpected stderr: {stderr}"
+    );
+}
+
+#[test]
+fn cli_repl_rejects_lines_with_extra_items() {
+    let repl = |input: &[u8]| {
+        let mut child = Command::new(frag_bin())
+            .arg("repl")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap_or_else(|error| panic!("failed to start CLI: {error}"));
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(input)
+            .expect("write REPL input");
+        child.wait_with_output().expect("REPL exits")
+    };
+
+    let output =
+        repl(b"1; input clk: bit; reg r: bit; on rising(clk) { r = 1; } wire w: bit; w = 0;\n");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "unexpected stderr: {stderr}");
+    assert!(!stderr.contains("panicked"), "unexpected stderr: {stderr}");
+    assert!(
+        stderr.contains("The REPL evaluates one expression or `const` declaration per line"),
+        "unexpected stderr: {stderr}"
+    );
+
+    let output = repl(
+        b"const k: u8 = 1; input clk: bit; reg r: bit; on rising(clk) { r = 1; }\nconst j: u8 = 2; const m: u8 = 3;\nk + 1\n:quit\n",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "unexpected stderr: {stderr}");
+    assert_eq!(
+        stderr.matches("The REPL evaluates one expression").count(),
+        2,
+        "unexpected stderr: {stderr}"
+    );
+    assert!(
+        stderr.contains("[E0001] Unknown signal `k`"),
+        "rejected const should not be kept: {stderr}"
+    );
+}
+
 fn fresh_probe_source() -> &'static str {
     r#"
 module FreshProbe123 {